@@ -0,0 +1,420 @@
+//! Refresh order of the entries computed from other entries of a feed
+//!
+//! Derived entries (`MostRecentOf`, `CappedFloored`, `CappedMostRecentOf`, `MedianOf`, the
+//! Scope TWAPs and the `Securitize` entries reading a `RedStone` entry) read the prices of their
+//! sources from the `OraclePrices` of the feed, so they must be refreshed after them to use their
+//! latest prices.
+//!
+//! The scope program orders its refreshes with [`get_refresh_order_by`], with its own parsing of
+//! the sources. Both parsings are tested against the feed of [`fixtures`].
+
+use anchor_lang::prelude::*;
+
+use crate::{OracleMappings, MAX_ENTRIES, MAX_ENTRIES_U16};
+
+// Price types computed from other entries, see `OracleType` in the scope program
+pub const SCOPE_TWAP_1H: u8 = 12;
+pub const MOST_RECENT_OF: u8 = 28;
+pub const SECURITIZE: u8 = 32;
+pub const CAPPED_FLOORED: u8 = 33;
+pub const CAPPED_MOST_RECENT_OF: u8 = 39;
+pub const SCOPE_TWAP_8H: u8 = 40;
+pub const SCOPE_TWAP_24H: u8 = 41;
+pub const MEDIAN_OF: u8 = 42;
+
+/// First byte of the generic data of the `Securitize` entries reading a `RedStone` entry
+const SECURITIZE_DATA_V1: u8 = 1;
+
+#[derive(AnchorDeserialize)]
+struct MostRecentOfData {
+    source_entries: [u16; 4],
+}
+
+#[derive(AnchorDeserialize)]
+struct CappedMostRecentOfData {
+    source_entries: [u16; 4],
+    _max_divergence_bps: u16,
+    _sources_max_age_s: u64,
+    cap_entry: u16,
+}
+
+#[derive(AnchorDeserialize)]
+struct CappedFlooredData {
+    source_entry: u16,
+    cap_entry: Option<u16>,
+    floor_entry: Option<u16>,
+}
+
+#[derive(AnchorDeserialize)]
+struct MedianOfData {
+    source_entries: [u16; 5],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshOrderError {
+    /// The entry is out of the feed (`>= MAX_ENTRIES`)
+    EntryOutOfRange(u16),
+    /// The price of the entry is, directly or transitively, computed from itself
+    DependencyCycle(u16),
+}
+
+/// Entries read by an entry of type `price_type` from its generic data, empty if it is not
+/// derived from other entries or if the generic data is invalid.
+///
+/// TWAP sources are stored in the mapping and not in the generic data.
+pub fn get_composite_source_entries(price_type: u8, generic_data: &[u8; 20]) -> Vec<u16> {
+    let mut data = &generic_data[..];
+    let sources = match price_type {
+        MOST_RECENT_OF => MostRecentOfData::deserialize(&mut data)
+            .map(|data| data.source_entries.to_vec())
+            .unwrap_or_default(),
+        CAPPED_MOST_RECENT_OF => CappedMostRecentOfData::deserialize(&mut data)
+            .map(|data| {
+                let mut sources = data.source_entries.to_vec();
+                sources.push(data.cap_entry);
+                sources
+            })
+            .unwrap_or_default(),
+        CAPPED_FLOORED => CappedFlooredData::deserialize(&mut data)
+            .map(|data| {
+                [Some(data.source_entry), data.cap_entry, data.floor_entry]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            })
+            .unwrap_or_default(),
+        MEDIAN_OF => MedianOfData::deserialize(&mut data)
+            .map(|data| data.source_entries.to_vec())
+            .unwrap_or_default(),
+        // Not a price source but the RedStone feed is the price account of this entry
+        SECURITIZE
+            if generic_data[0] == SECURITIZE_DATA_V1
+                && generic_data[3..].iter().all(|&b| b == 0) =>
+        {
+            vec![u16::from_le_bytes([generic_data[1], generic_data[2]])]
+        }
+        _ => Vec::new(),
+    };
+    // Unused slots are out of range
+    sources
+        .into_iter()
+        .filter(|&source| source < MAX_ENTRIES_U16)
+        .collect()
+}
+
+/// Entries the price of `entry_id` is computed from, empty if it is not a derived entry
+///
+/// Invalid generic data are considered as no source.
+pub fn get_price_sources(oracle_mappings: &OracleMappings, entry_id: usize) -> Vec<u16> {
+    let price_type = oracle_mappings.price_types[entry_id];
+    if price_type == 0 && oracle_mappings.price_info_accounts[entry_id] == Pubkey::default() {
+        // Unused entry
+        return Vec::new();
+    }
+    match price_type {
+        SCOPE_TWAP_1H | SCOPE_TWAP_8H | SCOPE_TWAP_24H => {
+            let source = oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id];
+            if source < MAX_ENTRIES_U16 {
+                vec![source]
+            } else {
+                Vec::new()
+            }
+        }
+        _ => get_composite_source_entries(price_type, &oracle_mappings.generic[entry_id]),
+    }
+}
+
+/// Whether the price of `entry_id` is computed from other entries of the feed
+pub fn is_derived_entry(oracle_mappings: &OracleMappings, entry_id: usize) -> bool {
+    !get_price_sources(oracle_mappings, entry_id).is_empty()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    NotVisited,
+    InProgress,
+    Done,
+}
+
+/// Order `tokens` so that every derived entry comes after its sources, adding the derived
+/// entries the requested ones transitively depend on.
+///
+/// Non-derived sources are not added as refreshing them requires their price accounts.
+/// Each entry appears once in the returned list. The result can be used as the token list of
+/// `refresh_price_list`.
+pub fn get_refresh_order(
+    oracle_mappings: &OracleMappings,
+    tokens: &[u16],
+) -> std::result::Result<Vec<u16>, RefreshOrderError> {
+    get_refresh_order_by(tokens, |entry_id| {
+        get_price_sources(oracle_mappings, entry_id)
+    })
+}
+
+/// [`get_refresh_order`] with the sources of each entry given by `get_price_sources`, empty for
+/// the entries not derived from other ones.
+pub fn get_refresh_order_by(
+    tokens: &[u16],
+    get_price_sources: impl Fn(usize) -> Vec<u16>,
+) -> std::result::Result<Vec<u16>, RefreshOrderError> {
+    let mut order = Vec::with_capacity(tokens.len());
+    let mut states = [VisitState::NotVisited; MAX_ENTRIES];
+    for &token in tokens {
+        // Iterative depth-first post-order, `true` once the sources of the entry are ordered
+        let mut to_visit = vec![(token, false)];
+        while let Some((entry, sources_ordered)) = to_visit.pop() {
+            let entry_id = usize::from(entry);
+            if entry_id >= MAX_ENTRIES {
+                return Err(RefreshOrderError::EntryOutOfRange(entry));
+            }
+            if sources_ordered {
+                states[entry_id] = VisitState::Done;
+                order.push(entry);
+                continue;
+            }
+            match states[entry_id] {
+                VisitState::Done => continue,
+                VisitState::InProgress => return Err(RefreshOrderError::DependencyCycle(entry)),
+                VisitState::NotVisited => {}
+            }
+            states[entry_id] = VisitState::InProgress;
+            to_visit.push((entry, true));
+            to_visit.extend(
+                get_price_sources(entry_id)
+                    .into_iter()
+                    .filter(|&source| !get_price_sources(source.into()).is_empty())
+                    .map(|source| (source, false)),
+            );
+        }
+    }
+    Ok(order)
+}
+
+/// Feed checked by the tests of both this crate and the scope program
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures {
+    use bytemuck::Zeroable;
+
+    use super::*;
+
+    const PYTH_PULL: u8 = 21;
+    const REDSTONE: u8 = 30;
+
+    pub struct DependenciesFixture {
+        pub oracle_mappings: Box<OracleMappings>,
+        /// Sources of the derived entries, the other entries have none
+        pub price_sources: Vec<(usize, Vec<u16>)>,
+        /// Token lists and their refresh order
+        pub refresh_orders: Vec<(Vec<u16>, Vec<u16>)>,
+    }
+
+    fn set_entry(oracle_mappings: &mut OracleMappings, entry_id: usize, price_type: u8) {
+        oracle_mappings.price_types[entry_id] = price_type;
+        oracle_mappings.price_info_accounts[entry_id] = if price_type == PYTH_PULL {
+            Pubkey::new_unique()
+        } else {
+            // Placeholder of the entries without price account
+            crate::ID
+        };
+    }
+
+    fn set_generic_data(
+        oracle_mappings: &mut OracleMappings,
+        entry_id: usize,
+        price_type: u8,
+        data: &[u8],
+    ) {
+        set_entry(oracle_mappings, entry_id, price_type);
+        oracle_mappings.generic[entry_id][..data.len()].copy_from_slice(data);
+    }
+
+    fn le_bytes(entries: &[u16]) -> Vec<u8> {
+        entries
+            .iter()
+            .flat_map(|entry| entry.to_le_bytes())
+            .collect()
+    }
+
+    /// Entries:
+    /// - 0, 1 Pyth entries and 2 a RedStone entry
+    /// - 3 a Securitize entry reading the RedStone entry 2
+    /// - 4 the most recent of 0 and 1
+    /// - 5 the 1h TWAP of 4
+    /// - 6 5 capped by 3
+    /// - 7 the most recent of 0 capped by 4
+    /// - 8 the median of 4, 6 and 7
+    /// - 9 a 24h TWAP without source and 10 a most recent of without valid sources
+    /// - 11 an unused entry with the generic data of a most recent of 0
+    pub fn dependencies_fixture() -> DependenciesFixture {
+        let mut oracle_mappings: Box<OracleMappings> = Box::new(Zeroable::zeroed());
+        for entry_id in 0..MAX_ENTRIES {
+            oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id] = u16::MAX;
+            oracle_mappings.ref_price[entry_id] = u16::MAX;
+        }
+        set_entry(&mut oracle_mappings, 0, PYTH_PULL);
+        set_entry(&mut oracle_mappings, 1, PYTH_PULL);
+        set_entry(&mut oracle_mappings, 2, REDSTONE);
+        set_generic_data(
+            &mut oracle_mappings,
+            3,
+            SECURITIZE,
+            &[SECURITIZE_DATA_V1, 2, 0],
+        );
+        let most_recent_of = le_bytes(&[0, 1, u16::MAX, u16::MAX]);
+        set_generic_data(&mut oracle_mappings, 4, MOST_RECENT_OF, &most_recent_of);
+        set_entry(&mut oracle_mappings, 5, SCOPE_TWAP_1H);
+        oracle_mappings.twap_source_or_ref_price_tolerance_bps[5] = 4;
+        // Source 5, cap 3, no floor
+        set_generic_data(&mut oracle_mappings, 6, CAPPED_FLOORED, &[5, 0, 1, 3, 0, 0]);
+        let capped_most_recent_of = [
+            le_bytes(&[0, u16::MAX, u16::MAX, u16::MAX, 100]),
+            60u64.to_le_bytes().to_vec(),
+            le_bytes(&[4]),
+        ]
+        .concat();
+        set_generic_data(
+            &mut oracle_mappings,
+            7,
+            CAPPED_MOST_RECENT_OF,
+            &capped_most_recent_of,
+        );
+        let median_of = le_bytes(&[4, 6, 7, u16::MAX, u16::MAX]);
+        set_generic_data(&mut oracle_mappings, 8, MEDIAN_OF, &median_of);
+        set_entry(&mut oracle_mappings, 9, SCOPE_TWAP_24H);
+        let no_sources = le_bytes(&[u16::MAX; 4]);
+        set_generic_data(&mut oracle_mappings, 10, MOST_RECENT_OF, &no_sources);
+        oracle_mappings.generic[11][..8].copy_from_slice(&le_bytes(&[
+            0,
+            u16::MAX,
+            u16::MAX,
+            u16::MAX,
+        ]));
+
+        DependenciesFixture {
+            oracle_mappings,
+            price_sources: vec![
+                (3, vec![2]),
+                (4, vec![0, 1]),
+                (5, vec![4]),
+                (6, vec![5, 3]),
+                (7, vec![0, 4]),
+                (8, vec![4, 6, 7]),
+            ],
+            refresh_orders: vec![
+                (vec![8], vec![4, 7, 3, 5, 6, 8]),
+                (vec![0, 6, 1], vec![0, 3, 4, 5, 6, 1]),
+                (vec![7, 5], vec![4, 7, 5]),
+                (vec![9, 10, 11, 2], vec![9, 10, 11, 2]),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+
+    use super::{fixtures::dependencies_fixture, *};
+
+    fn new_mappings() -> Box<OracleMappings> {
+        Box::new(Zeroable::zeroed())
+    }
+
+    fn set_most_recent_of(oracle_mappings: &mut OracleMappings, entry_id: usize, sources: &[u16]) {
+        let mut generic_data = [0; 20];
+        for (i, source) in [u16::MAX; 4].iter().enumerate() {
+            let source = sources.get(i).unwrap_or(source);
+            generic_data[2 * i..2 * i + 2].copy_from_slice(&source.to_le_bytes());
+        }
+        oracle_mappings.price_types[entry_id] = MOST_RECENT_OF;
+        oracle_mappings.generic[entry_id] = generic_data;
+    }
+
+    fn set_twap(oracle_mappings: &mut OracleMappings, entry_id: usize, source: u16) {
+        oracle_mappings.price_types[entry_id] = SCOPE_TWAP_1H;
+        oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id] = source;
+    }
+
+    fn position(order: &[u16], entry: u16) -> usize {
+        order.iter().position(|&e| e == entry).unwrap()
+    }
+
+    #[test]
+    fn test_fixture() {
+        let fixture = dependencies_fixture();
+        for entry_id in 0..MAX_ENTRIES {
+            let expected = fixture
+                .price_sources
+                .iter()
+                .find(|(derived_entry, _)| *derived_entry == entry_id)
+                .map(|(_, sources)| sources.clone())
+                .unwrap_or_default();
+            assert_eq!(
+                get_price_sources(&fixture.oracle_mappings, entry_id),
+                expected,
+                "sources of entry {entry_id}"
+            );
+        }
+        for (tokens, order) in fixture.refresh_orders {
+            assert_eq!(
+                get_refresh_order(&fixture.oracle_mappings, &tokens).unwrap(),
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn test_refresh_order_of_a_diamond() {
+        // 4 <- {2, 3} <- 1 <- 0
+        let mut oracle_mappings = new_mappings();
+        set_twap(&mut oracle_mappings, 1, 0);
+        set_most_recent_of(&mut oracle_mappings, 2, &[1]);
+        set_most_recent_of(&mut oracle_mappings, 3, &[1, 0]);
+        set_most_recent_of(&mut oracle_mappings, 4, &[2, 3]);
+
+        let order = get_refresh_order(&oracle_mappings, &[4, 0]).unwrap();
+        assert_eq!(order.len(), 5);
+        assert!(position(&order, 1) < position(&order, 2));
+        assert!(position(&order, 1) < position(&order, 3));
+        assert!(position(&order, 2) < position(&order, 4));
+        assert!(position(&order, 3) < position(&order, 4));
+    }
+
+    #[test]
+    fn test_refresh_order_adds_the_derived_sources() {
+        let mut oracle_mappings = new_mappings();
+        set_twap(&mut oracle_mappings, 1, 0);
+        set_most_recent_of(&mut oracle_mappings, 2, &[1]);
+        set_most_recent_of(&mut oracle_mappings, 3, &[2]);
+
+        // The non-derived source 0 is not added
+        assert_eq!(
+            get_refresh_order(&oracle_mappings, &[3]).unwrap(),
+            vec![1, 2, 3]
+        );
+        // Requested entries keep their relative order when independent
+        assert_eq!(
+            get_refresh_order(&oracle_mappings, &[0, 3, 1]).unwrap(),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_refresh_order_detects_cycles() {
+        let mut oracle_mappings = new_mappings();
+        set_most_recent_of(&mut oracle_mappings, 1, &[3]);
+        set_most_recent_of(&mut oracle_mappings, 2, &[1]);
+        set_twap(&mut oracle_mappings, 3, 2);
+        set_most_recent_of(&mut oracle_mappings, 4, &[5]);
+
+        assert!(matches!(
+            get_refresh_order(&oracle_mappings, &[2]),
+            Err(RefreshOrderError::DependencyCycle(_))
+        ));
+        assert!(get_refresh_order(&oracle_mappings, &[4]).is_ok());
+        assert_eq!(
+            get_refresh_order(&oracle_mappings, &[MAX_ENTRIES_U16]),
+            Err(RefreshOrderError::EntryOutOfRange(MAX_ENTRIES_U16))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::result_large_err)] //Needed because we can't change Anchor result type
 
+pub mod dependencies;
 pub mod program_id;
 
 // Reexports to deal with eventual conflicts
@@ -19,6 +20,8 @@ declare_id!(PROGRAM_ID);
 pub const MAX_ENTRIES_U16: u16 = 512;
 // Note: Need to be directly integer value to not confuse the IDL generator
 pub const MAX_ENTRIES: usize = 512;
+/// Maximum number of extension pages of a feed, each page holding `MAX_ENTRIES` more entries
+pub const MAX_EXTENSION_PAGES: usize = 3;
 
 #[zero_copy]
 #[derive(Debug, Eq, PartialEq, Default)]
@@ -62,6 +65,20 @@ impl Default for DatedPrice {
     }
 }
 
+impl DatedPrice {
+    /// Confidence interval (or standard deviation) of the price, with the exponent of the price.
+    ///
+    /// Only meaningful for entries of type `PythPull`, `PythPullEMA`, `PythLazer` and
+    /// `SwitchboardOnDemand`, other oracle types may use the underlying bytes for other purposes.
+    /// Consumers can use it to apply a conservative pricing such as `price ± k * confidence`.
+    pub fn confidence(&self) -> Price {
+        Price {
+            value: self._reserved[1],
+            exp: self.price.exp,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, TryFromPrimitive, IntoPrimitive)]
 #[repr(usize)]
 pub enum EmaType {
@@ -85,6 +102,25 @@ pub struct OraclePrices {
     pub prices: [DatedPrice; MAX_ENTRIES],
 }
 
+impl OraclePrices {
+    /// Price of the entry `entry_id` of a feed whose entries span several pages.
+    ///
+    /// `pages` are the `OraclePrices` accounts available to the caller with their address, the
+    /// right one is selected with [`Configuration::resolve_entry`]. Returns `None` if the page of
+    /// the entry is not linked or not provided.
+    pub fn get_paged_price<'a>(
+        configuration: &Configuration,
+        pages: &[(Pubkey, &'a OraclePrices)],
+        entry_id: usize,
+    ) -> Option<&'a DatedPrice> {
+        let (oracle_prices, index_in_page) = configuration.resolve_entry(entry_id)?;
+        pages
+            .iter()
+            .find(|(address, _)| *address == oracle_prices)
+            .map(|(_, page)| &page.prices[index_in_page])
+    }
+}
+
 #[zero_copy]
 #[derive(Debug, Eq, PartialEq)]
 pub struct EmaTwap {
@@ -177,7 +213,69 @@ pub struct Configuration {
     pub oracle_prices: Pubkey,
     pub tokens_metadata: Pubkey,
     pub oracle_twaps: Pubkey,
-    _padding: [u64; 1259],
+    pub admin_cached: Pubkey,
+    pub refresh_cpi_allowlist: [Pubkey; 8],
+    pub refresh_preceding_ix_allowlist: [Pubkey; 8],
+    pub mapping_update_delay_s: u64,
+    pub next_mapping_update_delay_s: u64,
+    pub next_mapping_update_delay_ts: u64,
+    pub mapping_admin: Pubkey,
+    pub metadata_admin: Pubkey,
+    pub twap_operator: Pubkey,
+    pub chainlink_resume_operator: Pubkey,
+    pub pauser: Pubkey,
+    pub feed_paused: u8,
+    _padding1: [u8; 7],
+    pub paused_groups_bitset: u64,
+    pub paused_entries_bitset: [u64; MAX_ENTRIES / 64],
+    pub extension_pages: [Pubkey; MAX_EXTENSION_PAGES],
+    pub extension_oracle_prices: [Pubkey; MAX_EXTENSION_PAGES],
+    pub base_configuration: Pubkey,
+    pub page_index: u8,
+    _padding2: [u8; 7],
+    pub audit_log: Pubkey,
+    _padding: [u64; 1125],
+}
+
+impl Configuration {
+    /// `OraclePrices` account holding the entry `entry_id` of the feed and the index of the entry
+    /// in it, `None` if the page of the entry is not linked.
+    ///
+    /// Entries `0..MAX_ENTRIES` are in the `oracle_prices` of this configuration, the following
+    /// ones in the `OraclePrices` of the extension pages, `MAX_ENTRIES` entries per page.
+    ///
+    /// The global index only exists for consumers: each page is an independent feed, whose
+    /// entries are addressed by the program with their index in the page, and the prices of a
+    /// page are only derived from or checked against entries of the same page.
+    pub fn resolve_entry(&self, entry_id: usize) -> Option<(Pubkey, usize)> {
+        let page = entry_id / MAX_ENTRIES;
+        let index_in_page = entry_id % MAX_ENTRIES;
+        let oracle_prices = if page == 0 {
+            self.oracle_prices
+        } else {
+            *self.extension_oracle_prices.get(page - 1)?
+        };
+        (oracle_prices != Pubkey::default()).then_some((oracle_prices, index_in_page))
+    }
+
+    /// Whether the refreshes of all the entries are paused
+    pub fn is_feed_paused(&self) -> bool {
+        self.feed_paused != 0
+    }
+
+    /// Whether the refreshes of the entry are paused, by itself, by one of its groups
+    /// (`group_ids_bitset` of its [`TokenMetadata`]) or with the feed.
+    ///
+    /// The price of a paused entry is kept as is and gets stale.
+    /// Returns false for an `entry_id` out of the feed (`>= MAX_ENTRIES`).
+    pub fn is_entry_paused(&self, entry_id: usize, group_ids_bitset: u64) -> bool {
+        let Some(paused_entries) = self.paused_entries_bitset.get(entry_id / 64) else {
+            return false;
+        };
+        self.is_feed_paused()
+            || paused_entries & (1 << (entry_id % 64)) != 0
+            || self.paused_groups_bitset & group_ids_bitset != 0
+    }
 }
 
 #[account(zero_copy)]
@@ -265,3 +363,84 @@ impl From<TryFromIntError> for ScopeError {
         ScopeError::OutOfRangeIntegralConversion
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_entry_paused() {
+        let mut configuration: Configuration = Zeroable::zeroed();
+        configuration.paused_entries_bitset[1] = 1 << 2;
+        assert!(configuration.is_entry_paused(66, 0));
+        assert!(!configuration.is_entry_paused(65, 0));
+        assert!(!configuration.is_entry_paused(MAX_ENTRIES, 0));
+
+        configuration.paused_groups_bitset = 1 << 3;
+        assert!(configuration.is_entry_paused(0, 1 << 3));
+
+        configuration.feed_paused = 1;
+        assert!(configuration.is_entry_paused(1, 0));
+    }
+
+    #[test]
+    fn test_resolve_entry() {
+        let mut configuration: Configuration = Zeroable::zeroed();
+        configuration.oracle_prices = Pubkey::new_unique();
+        configuration.extension_oracle_prices[0] = Pubkey::new_unique();
+
+        assert_eq!(
+            configuration.resolve_entry(5),
+            Some((configuration.oracle_prices, 5))
+        );
+        assert_eq!(
+            configuration.resolve_entry(MAX_ENTRIES + 5),
+            Some((configuration.extension_oracle_prices[0], 5))
+        );
+        // Unlinked and out of range pages
+        assert_eq!(configuration.resolve_entry(2 * MAX_ENTRIES), None);
+        assert_eq!(
+            configuration.resolve_entry((MAX_EXTENSION_PAGES + 1) * MAX_ENTRIES),
+            None
+        );
+
+        let mut base_page: Box<OraclePrices> = Box::new(Zeroable::zeroed());
+        let mut extension_page: Box<OraclePrices> = Box::new(Zeroable::zeroed());
+        base_page.prices[5].price.value = 1;
+        extension_page.prices[5].price.value = 2;
+        let pages = [
+            (configuration.oracle_prices, &*base_page),
+            (configuration.extension_oracle_prices[0], &*extension_page),
+        ];
+        let price = |entry_id| {
+            OraclePrices::get_paged_price(&configuration, &pages, entry_id)
+                .map(|price| price.price.value)
+        };
+        assert_eq!(price(5), Some(1));
+        assert_eq!(price(MAX_ENTRIES + 5), Some(2));
+        assert_eq!(price(2 * MAX_ENTRIES + 5), None);
+        // Linked page not provided
+        assert_eq!(
+            OraclePrices::get_paged_price(&configuration, &pages[..1], MAX_ENTRIES + 5),
+            None
+        );
+    }
+
+    #[test]
+    fn test_confidence_is_read_from_the_program_layout() {
+        let mut dated_price = DatedPrice {
+            price: Price {
+                value: 12345,
+                exp: 6,
+            },
+            ..Default::default()
+        };
+        // The program stores the confidence in the bytes 8..16 of the generic data following
+        // the price, the slot and the timestamp
+        bytemuck::bytes_of_mut(&mut dated_price)[32 + 8..32 + 16]
+            .copy_from_slice(&25_u64.to_le_bytes());
+
+        let confidence = dated_price.confidence();
+        assert_eq!((confidence.value, confidence.exp), (25, 6));
+    }
+}
@@ -201,6 +201,69 @@ pub enum ScopeError {
 
     #[msg("TWAP source index not set")]
     TwapSourceIndexNotSet,
+
+    #[msg("Price moved more than the configured maximum since the last update")]
+    PriceMoveTooLarge,
+
+    #[msg("The group id received is out of range")]
+    BadGroupId,
+
+    #[msg("The refresh allowlist is full")]
+    RefreshAllowlistFull,
+
+    #[msg("The price provided by the oracle is older than the configured maximum age")]
+    SourcePriceTooOld,
+
+    #[msg("The reference price is older than the configured maximum age")]
+    RefPriceTooOld,
+
+    #[msg("Mapping updates are timelocked and must be proposed first")]
+    MappingUpdateTimelocked,
+
+    #[msg("The mapping update delay has not elapsed yet")]
+    MappingUpdateNotReady,
+
+    #[msg("The signer does not hold the role required by the instruction")]
+    MissingRole,
+
+    #[msg("The refreshes of the feed are paused")]
+    FeedPaused,
+
+    #[msg("The refreshes of the entry are paused")]
+    EntryPaused,
+
+    #[msg("The feed must be paused first")]
+    FeedNotPaused,
+
+    #[msg("Invalid extension page")]
+    InvalidExtensionPage,
+
+    #[msg("The feed is linked to extension pages or is one")]
+    FeedHasExtensionPages,
+
+    #[msg("The audit log of the feed is missing or invalid")]
+    MissingAuditLog,
+
+    #[msg("Other entries depend on the removed or retyped entry")]
+    EntryHasDependents,
+
+    #[msg("The price of an entry depends on itself")]
+    DependencyCycle,
+
+    #[msg("Invalid max age (s) for MedianOf oracle")]
+    MedianOfInvalidMaxAge,
+
+    #[msg("Invalid minimum number of fresh sources for MedianOf oracle")]
+    MedianOfInvalidQuorum,
+
+    #[msg("Not enough fresh sources for MedianOf oracle")]
+    MedianOfQuorumNotMet,
+
+    #[msg("The price does not agree with enough reference prices")]
+    RefPriceQuorumNotMet,
+
+    #[msg("The provided list of mapping and metadata updates is empty")]
+    EmptyUpdateList,
 }
 
 impl<T> From<TryFromPrimitiveError<T>> for ScopeError
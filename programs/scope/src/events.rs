@@ -0,0 +1,228 @@
+//! Events emitted by the program
+//!
+//! Every refresh outcome, every change of the mapping timelock and every pause is reported
+//! through one of the events below so that indexers and alerting do not need to parse the
+//! free-form program logs.
+use anchor_lang::{error::ERROR_CODE_OFFSET, prelude::*};
+
+use crate::{oracles::OracleType, states::PauseTarget, Price, ScopeError};
+
+/// A price entry has been written with a new value
+#[event]
+pub struct PriceUpdated {
+    pub token: u16,
+    pub price_type: OracleType,
+    pub old_price: Price,
+    pub new_price: Price,
+    pub old_slot: u64,
+    pub new_slot: u64,
+    pub unix_timestamp: u64,
+    pub current_slot: u64,
+}
+
+/// A price entry has been left untouched by a refresh instruction
+#[event]
+pub struct PriceSkipped {
+    pub token: u16,
+    pub price_type: OracleType,
+    pub reason: PriceSkipReason,
+    /// Anchor error code of the failure, if the skip was caused by an error
+    pub error_code: Option<u32>,
+}
+
+/// A new price has been rejected as too far from its reference price
+#[event]
+pub struct RefPriceCheckFailed {
+    pub token: u16,
+    pub ref_token: u16,
+    pub price: Price,
+    pub ref_price: Price,
+    pub tolerance_bps: u16,
+}
+
+/// The price has been updated but the TWAPs of the entry could not be
+#[event]
+pub struct TwapUpdateFailed {
+    pub token: u16,
+    pub error_code: Option<u32>,
+}
+
+/// A mapping update has been proposed and can be executed from `earliest_execution_ts`
+#[event]
+pub struct MappingUpdateProposed {
+    pub configuration: Pubkey,
+    pub proposal_id: u64,
+    pub entries: Vec<u16>,
+    pub earliest_execution_ts: u64,
+}
+
+/// A proposed mapping update has been applied
+#[event]
+pub struct MappingUpdateExecuted {
+    pub configuration: Pubkey,
+    pub proposal_id: u64,
+}
+
+/// A proposed mapping update has been cancelled
+#[event]
+pub struct MappingUpdateCancelled {
+    pub configuration: Pubkey,
+    pub proposal_id: u64,
+}
+
+/// Refreshes of the target have been paused or resumed
+#[event]
+pub struct PauseUpdated {
+    pub configuration: Pubkey,
+    pub target: PauseTarget,
+    pub paused: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSkipReason {
+    /// No mapping is set for the entry
+    NoMapping,
+    /// The oracle returned a price that failed its type-specific validation
+    ValidationFailed,
+    /// The oracle returned a price older than accepted
+    OutdatedPrice,
+    /// The confidence interval (or standard deviation) of the price is too wide
+    ConfidenceIntervalTooWide,
+    /// The market of the asset is closed
+    OutsideMarketHours,
+    /// One of the provided accounts is not the expected one or cannot be read
+    InvalidAccount,
+    /// The existing price has been suspended instead of being updated
+    PriceSuspended,
+    /// The new price moved too far from the stored one
+    PriceMoveTooLarge,
+    /// The refreshes of the entry are paused
+    Paused,
+    /// Any other failure, see the error code
+    Other,
+}
+
+impl PriceSkipReason {
+    pub fn from_error(err: &Error) -> Self {
+        scope_error(err).map(Self::from).unwrap_or(Self::Other)
+    }
+}
+
+impl From<ScopeError> for PriceSkipReason {
+    fn from(err: ScopeError) -> Self {
+        match err {
+            ScopeError::PriceNotValid
+            | ScopeError::FixedPriceInvalid
+            | ScopeError::SwitchboardOnDemandError
+            | ScopeError::KTokenUnderlyingPriceNotValid
+            | ScopeError::KTokenHoldingsCalculationError
+            | ScopeError::StakeFeeTooHigh
+            | ScopeError::MostRecentOfMaxDivergenceBpsViolated
+            | ScopeError::TwapNotEnoughSamplesInPeriod
+            | ScopeError::InvalidGenericData
+            | ScopeError::PythLazerInvalidFeedId
+            | ScopeError::PythLazerPriceNotPresent
+            | ScopeError::PythLazerBestBidPriceNotPresent
+            | ScopeError::PythLazerBestAskPriceNotPresent
+            | ScopeError::PythLazerInvalidAskBidPrices
+            | ScopeError::PythLazerExponentNotPresent
+            | ScopeError::PythLazerUnexpectedExponent
+            | ScopeError::RefPriceQuorumNotMet => Self::ValidationFailed,
+            ScopeError::BadTimestamp
+            | ScopeError::BadSlot
+            | ScopeError::MostRecentOfMaxAgeViolated
+            | ScopeError::MedianOfQuorumNotMet
+            | ScopeError::SourcePriceTooOld
+            | ScopeError::RefPriceTooOld => Self::OutdatedPrice,
+            ScopeError::ConfidenceIntervalCheckFailed => Self::ConfidenceIntervalTooWide,
+            ScopeError::OutsideMarketHours => Self::OutsideMarketHours,
+            ScopeError::PriceMoveTooLarge => Self::PriceMoveTooLarge,
+            ScopeError::FeedPaused | ScopeError::EntryPaused => Self::Paused,
+            ScopeError::UnexpectedAccount
+            | ScopeError::AccountsAndTokenMismatch
+            | ScopeError::InvalidAccountDiscriminator
+            | ScopeError::UnableToDeserializeAccount
+            | ScopeError::WrongAccountOwner => Self::InvalidAccount,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Anchor error code of an error, `None` for native program errors
+pub fn error_code(err: &Error) -> Option<u32> {
+    match err {
+        Error::AnchorError(anchor_error) => Some(anchor_error.error_code_number),
+        Error::ProgramError(_) => None,
+    }
+}
+
+fn scope_error(err: &Error) -> Option<ScopeError> {
+    error_code(err)
+        .and_then(|code| code.checked_sub(ERROR_CODE_OFFSET))
+        .and_then(|code| ScopeError::try_from(code).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_reason_of_scope_errors() {
+        let reason = |err: ScopeError| PriceSkipReason::from_error(&err.into());
+
+        assert_eq!(
+            reason(ScopeError::PriceNotValid),
+            PriceSkipReason::ValidationFailed
+        );
+        assert_eq!(
+            reason(ScopeError::BadTimestamp),
+            PriceSkipReason::OutdatedPrice
+        );
+        assert_eq!(
+            reason(ScopeError::ConfidenceIntervalCheckFailed),
+            PriceSkipReason::ConfidenceIntervalTooWide
+        );
+        assert_eq!(
+            reason(ScopeError::PriceMoveTooLarge),
+            PriceSkipReason::PriceMoveTooLarge
+        );
+        assert_eq!(reason(ScopeError::EntryPaused), PriceSkipReason::Paused);
+        assert_eq!(
+            reason(ScopeError::WrongAccountOwner),
+            PriceSkipReason::InvalidAccount
+        );
+        assert_eq!(reason(ScopeError::BadTokenNb), PriceSkipReason::Other);
+    }
+
+    #[test]
+    fn test_skip_reason_of_other_errors() {
+        let anchor_error: Error = ErrorCode::AccountDidNotDeserialize.into();
+        let program_error: Error = ProgramError::InvalidArgument.into();
+
+        assert_eq!(
+            PriceSkipReason::from_error(&anchor_error),
+            PriceSkipReason::Other
+        );
+        assert_eq!(
+            PriceSkipReason::from_error(&program_error),
+            PriceSkipReason::Other
+        );
+    }
+
+    #[test]
+    fn test_error_code() {
+        let scope_error: Error = ScopeError::PriceNotValid.into();
+        let anchor_error: Error = ErrorCode::AccountDidNotDeserialize.into();
+        let program_error: Error = ProgramError::InvalidArgument.into();
+
+        assert_eq!(
+            error_code(&scope_error),
+            Some(ScopeError::PriceNotValid.into())
+        );
+        assert_eq!(
+            error_code(&anchor_error),
+            Some(ErrorCode::AccountDidNotDeserialize.into())
+        );
+        assert_eq!(error_code(&program_error), None);
+    }
+}
@@ -1,6 +1,12 @@
 use anchor_lang::{prelude::*, Accounts};
 
-use crate::{oracles::check_context, states::configuration::Configuration};
+use crate::{
+    oracles::check_context,
+    states::{
+        audit_log::get_audit_log, configuration::Configuration, AuditLog, AuditRecordKind,
+        AUDIT_NO_ENTRY,
+    },
+};
 
 #[derive(Accounts)]
 #[instruction(feed_name: String)]
@@ -9,6 +15,10 @@ pub struct ApproveAdminCached<'info> {
 
     #[account(mut, seeds = [b"conf", feed_name.as_bytes()], bump, has_one = admin_cached)]
     pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
 }
 
 pub fn process(ctx: Context<ApproveAdminCached>, feed_name: String) -> Result<()> {
@@ -23,6 +33,17 @@ pub fn process(ctx: Context<ApproveAdminCached>, feed_name: String) -> Result<()
         feed_name
     );
 
+    if let Some(audit_log) = get_audit_log(configuration, &ctx.accounts.audit_log)? {
+        audit_log.load_mut()?.push(
+            Clock::get()?.slot,
+            ctx.accounts.admin_cached.key(),
+            AUDIT_NO_ENTRY,
+            AuditRecordKind::ApproveAdminCached,
+            configuration.admin.as_ref(),
+            configuration.admin_cached.as_ref(),
+        );
+    }
+
     configuration.admin = configuration.admin_cached;
 
     Ok(())
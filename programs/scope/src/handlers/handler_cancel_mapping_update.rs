@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::MappingUpdateCancelled,
+    handlers::handler_update_mapping_and_metadata::check_update_roles,
+    oracles::check_context,
+    states::{Configuration, PendingMappingUpdate},
+    utils::pdas::seeds,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String, proposal_id: u64)]
+pub struct CancelMappingUpdate<'info> {
+    /// Admin, or holder of the roles required by the updates
+    pub admin: Signer<'info>,
+
+    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [seeds::PENDING_MAPPING_UPDATE, configuration.key().as_ref(), &proposal_id.to_le_bytes()],
+        bump,
+        has_one = configuration,
+        has_one = proposer,
+    )]
+    pub pending_mapping_update: Account<'info, PendingMappingUpdate>,
+
+    /// Refunded the rent of the proposal
+    /// CHECK: Checked against the proposal
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+}
+
+pub fn process(ctx: Context<CancelMappingUpdate>, proposal_id: u64) -> Result<()> {
+    check_context(&ctx)?;
+    check_update_roles(
+        &*ctx.accounts.configuration.load()?,
+        ctx.accounts.admin.key,
+        &ctx.accounts.pending_mapping_update.updates,
+    )?;
+
+    msg!("Cancelling mapping update {proposal_id}");
+
+    emit!(MappingUpdateCancelled {
+        configuration: ctx.accounts.configuration.key(),
+        proposal_id,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    states::{
+        audit_log::get_audit_log, AuditLog, Configuration, OracleMappings, OraclePrices,
+        OracleTwaps, TokenMetadatas,
+    },
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct CloseFeed<'info> {
+    pub admin: Signer<'info>,
+
+    /// CHECK: Only receives the rent of the closed accounts
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = admin,
+        has_one = oracle_mappings,
+        has_one = oracle_prices,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    #[account(mut, close = recipient)]
+    pub oracle_mappings: AccountLoader<'info, OracleMappings>,
+
+    #[account(mut, close = recipient, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, OraclePrices>,
+
+    #[account(mut, close = recipient, has_one = oracle_mappings, has_one = oracle_prices)]
+    pub oracle_twaps: AccountLoader<'info, OracleTwaps>,
+
+    #[account(mut, close = recipient)]
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
+
+    /// Required if the feed has an audit log
+    #[account(mut, close = recipient)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(ctx: Context<CloseFeed>, feed_name: String) -> Result<()> {
+    check_context(&ctx)?;
+
+    let configuration = ctx.accounts.configuration.load()?;
+    if let Err(e) = configuration.check_can_close() {
+        msg!(
+            "Feed {} must be paused and unlinked from its pages before being closed",
+            feed_name
+        );
+        return Err(e.into());
+    }
+    // Only the audit log of the feed is closed with it
+    if let Some(audit_log) = &ctx.accounts.audit_log {
+        require_keys_eq!(
+            audit_log.key(),
+            configuration.audit_log,
+            ScopeError::UnexpectedAccount
+        );
+    }
+    get_audit_log(&configuration, &ctx.accounts.audit_log)?;
+
+    msg!(
+        "Closing feed {}, rent sent to {}",
+        feed_name,
+        ctx.accounts.recipient.key()
+    );
+
+    Ok(())
+}
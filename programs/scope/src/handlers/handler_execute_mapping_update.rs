@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::MappingUpdateExecuted,
+    handlers::handler_update_mapping_and_metadata::{apply_updates, check_update_roles},
+    states::{
+        audit_log::split_audit_log, Configuration, OracleMappings, OraclePrices, OracleTwaps,
+        PendingMappingUpdate, TokenMetadatas,
+    },
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+/// Handler expects as remaining accounts the `price_info` accounts given at proposal time,
+/// followed by the audit log of the feed if it has one
+#[derive(Accounts)]
+#[instruction(feed_name: String, proposal_id: u64)]
+pub struct ExecuteMappingUpdate<'info> {
+    /// Admin, or holder of the roles required by the updates
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        has_one = oracle_prices,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [seeds::PENDING_MAPPING_UPDATE, configuration.key().as_ref(), &proposal_id.to_le_bytes()],
+        bump,
+        has_one = configuration,
+        has_one = proposer,
+    )]
+    pub pending_mapping_update: Account<'info, PendingMappingUpdate>,
+
+    /// Refunded the rent of the proposal
+    /// CHECK: Checked against the proposal
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub oracle_mappings: AccountLoader<'info, OracleMappings>,
+
+    #[account(mut)]
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
+
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, OraclePrices>,
+
+    #[account(mut, has_one = oracle_mappings, has_one = oracle_prices)]
+    pub oracle_twaps: AccountLoader<'info, OracleTwaps>,
+}
+
+pub fn process(ctx: Context<ExecuteMappingUpdate>, proposal_id: u64) -> Result<()> {
+    let pending = &ctx.accounts.pending_mapping_update;
+    let configuration = ctx.accounts.configuration.load()?;
+    check_update_roles(&configuration, ctx.accounts.admin.key, &pending.updates)?;
+    let (price_infos, audit_log) = split_audit_log(&configuration, ctx.remaining_accounts)?;
+
+    let now = u64::try_from(Clock::get()?.unix_timestamp).unwrap();
+    if now < pending.earliest_execution_ts {
+        msg!(
+            "Mapping update {proposal_id} cannot be executed before {}",
+            pending.earliest_execution_ts
+        );
+        return err!(ScopeError::MappingUpdateNotReady);
+    }
+
+    let received_price_infos: Vec<Pubkey> = price_infos.iter().map(|acc| acc.key()).collect();
+    if received_price_infos != pending.price_info_accounts {
+        msg!("Price accounts differ from the proposed ones");
+        return err!(ScopeError::UnexpectedAccount);
+    }
+
+    msg!("Executing mapping update {proposal_id}");
+
+    apply_updates(
+        &ctx.accounts.oracle_mappings,
+        &ctx.accounts.tokens_metadata,
+        &ctx.accounts.oracle_prices,
+        &ctx.accounts.oracle_twaps,
+        audit_log.as_ref(),
+        ctx.accounts.admin.key(),
+        price_infos,
+        pending.updates.clone(),
+    )?;
+
+    emit!(MappingUpdateExecuted {
+        configuration: ctx.accounts.configuration.key(),
+        proposal_id,
+    });
+
+    Ok(())
+}
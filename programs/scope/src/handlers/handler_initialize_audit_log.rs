@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    states::{AuditLog, Configuration},
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct InitializeAuditLog<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    // Account is pre-reserved/paid outside the program
+    #[account(zero)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+}
+
+pub fn process(ctx: Context<InitializeAuditLog>) -> Result<()> {
+    check_context(&ctx)?;
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    // Replacing the audit log would drop the history of the feed
+    if configuration.audit_log != Pubkey::default() {
+        msg!("Feed already has the audit log {}", configuration.audit_log);
+        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+    }
+
+    let mut audit_log = ctx.accounts.audit_log.load_init()?;
+    audit_log.configuration = ctx.accounts.configuration.key();
+    configuration.audit_log = ctx.accounts.audit_log.key();
+
+    msg!("Audit log set to {}", configuration.audit_log);
+
+    Ok(())
+}
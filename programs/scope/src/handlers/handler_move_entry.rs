@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::{check_context, remap_composite_source_entries},
+    states::{
+        audit_log::get_audit_log, AuditLog, AuditRecordKind, Configuration, OracleMappings,
+        OraclePrices, OracleTwaps, PauseTarget, Role, TokenMetadata, TokenMetadatas,
+    },
+    utils::pdas::seeds,
+    ScopeError, MAX_ENTRIES, MAX_ENTRIES_U16,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct MoveEntry<'info> {
+    /// Admin, or holder of both the mapping admin and metadata admin roles
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        has_one = oracle_prices,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    #[account(mut)]
+    pub oracle_mappings: AccountLoader<'info, OracleMappings>,
+
+    #[account(mut)]
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
+
+    #[account(mut, has_one = oracle_mappings)]
+    pub oracle_prices: AccountLoader<'info, OraclePrices>,
+
+    #[account(mut, has_one = oracle_mappings, has_one = oracle_prices)]
+    pub oracle_twaps: AccountLoader<'info, OracleTwaps>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+/// Relocate the mapping, metadata, price and TWAP of `source` to the unused `destination`.
+///
+/// When moving (`keep_source == false`), all the references to `source` (ref prices, TWAP
+/// sources and composite oracle sources) are rewritten to `destination` and `source` is reset.
+/// When copying, `source` and the references to it are left untouched.
+pub fn process(
+    ctx: Context<MoveEntry>,
+    source: u16,
+    destination: u16,
+    keep_source: bool,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    require_gt!(MAX_ENTRIES_U16, source, ScopeError::BadTokenNb);
+    require_gt!(MAX_ENTRIES_U16, destination, ScopeError::BadTokenNb);
+    if source == destination {
+        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+    }
+
+    let mut configuration = ctx.accounts.configuration.load_mut()?;
+    let signer = ctx.accounts.admin.key();
+    if !configuration.has_role(Role::MappingAdmin, &signer)
+        || !configuration.has_role(Role::MetadataAdmin, &signer)
+    {
+        return err!(ScopeError::MissingRole);
+    }
+    // Relocating entries changes what consumers read at both indexes
+    let now = u64::try_from(Clock::get()?.unix_timestamp).unwrap();
+    if configuration.get_mapping_update_delay_s(now) != 0 {
+        msg!("Entries cannot be relocated while mapping updates are timelocked");
+        return err!(ScopeError::MappingUpdateTimelocked);
+    }
+
+    let mut oracle_mappings = ctx.accounts.oracle_mappings.load_mut()?;
+    let mut tokens_metadata = ctx.accounts.tokens_metadata.load_mut()?;
+    let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
+    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
+
+    let src = usize::from(source);
+    let dst = usize::from(destination);
+    if !oracle_mappings.is_entry_used(src) {
+        msg!("Source entry {source} is not used");
+        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+    }
+    if oracle_mappings.is_entry_used(dst) {
+        msg!("Destination entry {destination} is already used");
+        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+    }
+
+    msg!(
+        "{} entry {source} - \"{}\" to {destination}",
+        if keep_source { "Copying" } else { "Moving" },
+        tokens_metadata.metadatas_array[src].get_name()
+    );
+
+    relocate_entry(
+        &mut configuration,
+        &mut oracle_mappings,
+        &mut tokens_metadata.metadatas_array,
+        &mut oracle_prices,
+        &mut oracle_twaps,
+        source,
+        destination,
+        keep_source,
+    )?;
+
+    if let Some(audit_log) = get_audit_log(&configuration, &ctx.accounts.audit_log)? {
+        let kind = if keep_source {
+            AuditRecordKind::CopyEntry
+        } else {
+            AuditRecordKind::MoveEntry
+        };
+        audit_log.load_mut()?.push(
+            Clock::get()?.slot,
+            signer,
+            destination,
+            kind,
+            &source.to_le_bytes(),
+            &destination.to_le_bytes(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Copy the state of `source` to `destination`, then rewrite the references to `source` and
+/// reset it unless `keep_source`.
+#[allow(clippy::too_many_arguments)]
+fn relocate_entry(
+    configuration: &mut Configuration,
+    oracle_mappings: &mut OracleMappings,
+    metadatas: &mut [TokenMetadata],
+    oracle_prices: &mut OraclePrices,
+    oracle_twaps: &mut OracleTwaps,
+    source: u16,
+    destination: u16,
+    keep_source: bool,
+) -> Result<()> {
+    let src = usize::from(source);
+    let dst = usize::from(destination);
+
+    // Relocate the state of the entry
+    oracle_mappings.price_info_accounts[dst] = oracle_mappings.price_info_accounts[src];
+    oracle_mappings.price_types[dst] = oracle_mappings.price_types[src];
+    oracle_mappings.twap_source_or_ref_price_tolerance_bps[dst] =
+        oracle_mappings.twap_source_or_ref_price_tolerance_bps[src];
+    oracle_mappings.twap_enabled_bitmask[dst] = oracle_mappings.twap_enabled_bitmask[src];
+    oracle_mappings.ref_price[dst] = oracle_mappings.ref_price[src];
+    oracle_mappings.generic[dst] = oracle_mappings.generic[src];
+    metadatas[dst] = metadatas[src];
+    oracle_prices.prices[dst] = oracle_prices.prices[src];
+    oracle_twaps.twaps[dst] = oracle_twaps.twaps[src];
+    let source_paused = configuration.is_entry_individually_paused(src);
+    configuration.set_paused(PauseTarget::Entry(destination), source_paused)?;
+
+    if !keep_source {
+        let remap = |entry: u16| if entry == source { destination } else { entry };
+        for entry_id in 0..MAX_ENTRIES {
+            if !oracle_mappings.is_entry_used(entry_id) || entry_id == src {
+                continue;
+            }
+            if oracle_mappings.ref_price[entry_id] == source {
+                msg!("Rewriting ref price of entry {entry_id}");
+                oracle_mappings.ref_price[entry_id] = destination;
+            }
+            if oracle_mappings.get_twap_source(entry_id) == Some(src) {
+                msg!("Rewriting TWAP source of entry {entry_id}");
+                oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id] = destination;
+            }
+            let price_type = oracle_mappings.get_entry_type(entry_id)?;
+            let generic_data = remap_composite_source_entries(
+                price_type,
+                &oracle_mappings.generic[entry_id],
+                remap,
+            )?;
+            if generic_data != oracle_mappings.generic[entry_id] {
+                msg!("Rewriting sources of entry {entry_id}");
+                oracle_mappings.generic[entry_id] = generic_data;
+            }
+            let metadata = &mut metadatas[entry_id];
+            let len = usize::from(metadata.additional_ref_prices_len);
+            if metadata.additional_ref_prices[..len].contains(&source) {
+                msg!("Rewriting additional ref prices of entry {entry_id}");
+                metadata.additional_ref_prices[..len]
+                    .iter_mut()
+                    .for_each(|entry| *entry = remap(*entry));
+            }
+        }
+
+        oracle_mappings.reset_entry(src);
+        metadatas[src].reset();
+        oracle_prices.reset_entry(src);
+        oracle_twaps.reset_entry(src);
+        configuration.set_paused(PauseTarget::Entry(source), false)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        oracles::OracleType,
+        utils::{
+            dependencies::get_price_sources,
+            test_utils::{new_feed, set_most_recent_of_entry, set_pyth_entry, set_twap_entry},
+        },
+        Price,
+    };
+
+    struct Feed {
+        configuration: Box<Configuration>,
+        oracle_mappings: Box<OracleMappings>,
+        metadatas: Vec<TokenMetadata>,
+        oracle_prices: Box<OraclePrices>,
+        oracle_twaps: Box<OracleTwaps>,
+    }
+
+    impl Feed {
+        /// Paused Pyth entry 0 used by:
+        /// - 1 the most recent of 0
+        /// - 2 the TWAP of 0
+        /// - 3 a Pyth entry checked against 0 by both its ref price and additional ref prices
+        fn new() -> Self {
+            let (oracle_mappings, metadatas) = new_feed();
+            let mut feed = Feed {
+                configuration: Box::new(bytemuck::Zeroable::zeroed()),
+                oracle_mappings,
+                metadatas,
+                oracle_prices: Box::new(bytemuck::Zeroable::zeroed()),
+                oracle_twaps: Box::new(bytemuck::Zeroable::zeroed()),
+            };
+            let oracle_mappings = &mut feed.oracle_mappings;
+            set_pyth_entry(oracle_mappings, 0);
+            set_pyth_entry(oracle_mappings, 3);
+            set_most_recent_of_entry(oracle_mappings, 1, &[0]);
+            set_twap_entry(oracle_mappings, 2, 0);
+            oracle_mappings.set_ref_price(3, Some(0));
+            feed.metadatas[0].set_name("SOL/USD");
+            feed.metadatas[3].additional_ref_prices[0] = 0;
+            feed.metadatas[3].additional_ref_prices_len = 1;
+            feed.oracle_prices.prices[0].price = Price { value: 42, exp: 0 };
+            feed.configuration
+                .set_paused(PauseTarget::Entry(0), true)
+                .unwrap();
+            feed
+        }
+
+        fn relocate(&mut self, source: u16, destination: u16, keep_source: bool) {
+            relocate_entry(
+                &mut self.configuration,
+                &mut self.oracle_mappings,
+                &mut self.metadatas,
+                &mut self.oracle_prices,
+                &mut self.oracle_twaps,
+                source,
+                destination,
+                keep_source,
+            )
+            .unwrap();
+        }
+
+        /// Entries 1, 2 and 3 reference `entry`
+        fn assert_references(&self, entry: u16) {
+            assert_eq!(get_price_sources(&self.oracle_mappings, 1), vec![entry]);
+            assert_eq!(get_price_sources(&self.oracle_mappings, 2), vec![entry]);
+            assert_eq!(self.oracle_mappings.get_ref_price(3), Some(entry));
+            assert_eq!(self.metadatas[3].get_additional_ref_prices(), &[entry]);
+        }
+
+        fn assert_relocated_to(&self, entry: u16) {
+            let entry_id = usize::from(entry);
+            assert_eq!(
+                self.oracle_mappings.get_entry_type(entry_id),
+                Ok(OracleType::PythPull)
+            );
+            assert_eq!(self.metadatas[entry_id].get_name(), "SOL/USD");
+            assert_eq!(self.oracle_prices.prices[entry_id].price.value, 42);
+            assert!(self.configuration.is_entry_individually_paused(entry_id));
+        }
+    }
+
+    #[test]
+    fn test_move_rewrites_the_references() {
+        let mut feed = Feed::new();
+        feed.relocate(0, 10, false);
+
+        feed.assert_relocated_to(10);
+        feed.assert_references(10);
+        assert!(!feed.oracle_mappings.is_entry_used(0));
+        assert_eq!(feed.metadatas[0], TokenMetadata::default());
+        assert_eq!(feed.oracle_prices.prices[0].price.value, 0);
+        assert!(!feed.configuration.is_entry_individually_paused(0));
+    }
+
+    #[test]
+    fn test_copy_keeps_the_source() {
+        let mut feed = Feed::new();
+        feed.relocate(0, 10, true);
+
+        feed.assert_relocated_to(10);
+        feed.assert_relocated_to(0);
+        feed.assert_references(0);
+    }
+}
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::MappingUpdateProposed,
+    handlers::handler_update_mapping_and_metadata::{
+        check_update_roles, check_updates, UpdateOracleMappingAndMetadataEntriesWithId,
+    },
+    states::{Configuration, OracleMappings, PendingMappingUpdate, TokenMetadatas},
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+/// Handler expects as remaining accounts a `price_info` for each
+/// `UpdateOracleMappingAndMetadataEntry::MappingConfig`, the same ones will be expected at execution
+///
+/// The updates are checked against the current feed, they are checked again at execution
+#[derive(Accounts)]
+#[instruction(
+    feed_name: String,
+    proposal_id: u64,
+    updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
+)]
+pub struct ProposeMappingUpdate<'info> {
+    /// Admin, or holder of the roles required by the updates
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    pub oracle_mappings: AccountLoader<'info, OracleMappings>,
+
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
+
+    #[account(
+        init,
+        seeds = [seeds::PENDING_MAPPING_UPDATE, configuration.key().as_ref(), &proposal_id.to_le_bytes()],
+        bump,
+        space = 8 + PendingMappingUpdate::size(&updates)?,
+        payer = admin,
+    )]
+    pub pending_mapping_update: Account<'info, PendingMappingUpdate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(
+    ctx: Context<ProposeMappingUpdate>,
+    proposal_id: u64,
+    updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
+) -> Result<()> {
+    require_eq!(
+        ctx.remaining_accounts.len(),
+        PendingMappingUpdate::price_info_accounts_count(&updates),
+        ScopeError::MissingPriceAccount
+    );
+
+    let clock = Clock::get()?;
+    let now = u64::try_from(clock.unix_timestamp).unwrap();
+    let delay_s = {
+        let configuration = ctx.accounts.configuration.load()?;
+        check_update_roles(&configuration, ctx.accounts.admin.key, &updates)?;
+        configuration.get_mapping_update_delay_s(now)
+    };
+    check_updates(
+        &*ctx.accounts.oracle_mappings.load()?,
+        &ctx.accounts.tokens_metadata.load()?.metadatas_array,
+        ctx.remaining_accounts,
+        &updates,
+        &clock,
+    )?;
+    let earliest_execution_ts = now.saturating_add(delay_s);
+
+    msg!(
+        "Proposing mapping update {proposal_id} with {} entries, executable from {earliest_execution_ts}",
+        updates.len()
+    );
+
+    let configuration = ctx.accounts.configuration.key();
+    let entries = updates.iter().map(|entry| entry.entry_id).collect();
+    ctx.accounts
+        .pending_mapping_update
+        .set_inner(PendingMappingUpdate {
+            configuration,
+            proposer: ctx.accounts.admin.key(),
+            proposal_id,
+            earliest_execution_ts,
+            price_info_accounts: ctx.remaining_accounts.iter().map(|acc| acc.key()).collect(),
+            updates,
+        });
+
+    emit!(MappingUpdateProposed {
+        configuration,
+        proposal_id,
+        entries,
+        earliest_execution_ts,
+    });
+
+    Ok(())
+}
@@ -5,6 +5,7 @@ use chainlink_streams_report::report::{
 use solana_program::program::{get_return_data, invoke};
 
 use crate::{
+    events::{self, PriceSkipReason, PriceSkipped, PriceUpdated, TwapUpdateFailed},
     oracles::{
         chainlink::{
             self,
@@ -15,8 +16,8 @@ use crate::{
         },
         OracleType,
     },
-    states::{OracleMappings, OraclePrices, OracleTwaps},
-    utils::price_impl::check_ref_price_difference,
+    states::{Configuration, OracleMappings, OraclePrices, OracleTwaps, TokenMetadatas},
+    utils::price_impl::check_entry_ref_price,
     ScopeError,
 };
 
@@ -51,6 +52,18 @@ pub struct RefreshChainlinkPrice<'info> {
     /// CHECK: The program ID is validated by the verifier program.
     #[account(address = VERIFIER_PROGRAM_ID)]
     pub verifier_program_id: AccountInfo<'info>,
+
+    /// Holds the pause state of the feed
+    #[account(
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Per-entry refresh settings
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
 }
 
 pub fn refresh_chainlink_price<'info>(
@@ -58,6 +71,18 @@ pub fn refresh_chainlink_price<'info>(
     token: u16,
     serialized_chainlink_report: Vec<u8>,
 ) -> Result<()> {
+    // 0 - refuse paused entries before paying for the verification
+    let token_idx: usize = token.into();
+    let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+    let metadata = tokens_metadata
+        .metadatas_array
+        .get(token_idx)
+        .ok_or(ScopeError::BadTokenNb)?;
+    ctx.accounts
+        .configuration
+        .load()?
+        .check_refresh_not_paused(token_idx, metadata.group_ids_bitset)?;
+
     // 1 - verify the report
     let program_id = ctx.accounts.verifier_program_id.key();
     let verifier_account = ctx.accounts.verifier_account.key();
@@ -101,7 +126,6 @@ pub fn refresh_chainlink_price<'info>(
     let oracle_mappings = ctx.accounts.oracle_mappings.load()?;
     let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
     let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
-    let token_idx: usize = token.into();
 
     let price_update_result = {
         let oracle_mapping = *oracle_mappings
@@ -199,6 +223,10 @@ pub fn refresh_chainlink_price<'info>(
                     oracle_mappings.twap_enabled_bitmask[token_idx],
                 ) {
                     msg!("Error while updating TWAP of token {token_idx}: {e:?}",);
+                    emit!(TwapUpdateFailed {
+                        token,
+                        error_code: events::error_code(&e),
+                    });
                 }
             }
             _ => {}
@@ -214,20 +242,32 @@ pub fn refresh_chainlink_price<'info>(
             dated_price_ref.last_updated_slot,
             clock.slot,
         );
+        match price_update_result {
+            PriceUpdateResult::Updated => emit!(PriceUpdated {
+                token,
+                price_type,
+                old_price: old_price.price,
+                new_price: dated_price_ref.price,
+                old_slot: old_price.last_updated_slot,
+                new_slot: dated_price_ref.last_updated_slot,
+                unix_timestamp: dated_price_ref.unix_timestamp,
+                current_slot: clock.slot,
+            }),
+            PriceUpdateResult::SuspendExistingPrice => emit!(PriceSkipped {
+                token,
+                price_type,
+                reason: PriceSkipReason::PriceSuspended,
+                error_code: None,
+            }),
+        }
 
         price_update_result
     };
 
-    // check that the price is close enough to the ref price if there is a ref price
-    match price_update_result {
-        PriceUpdateResult::Updated if oracle_mappings.ref_price[token_idx] != u16::MAX => {
-            let new_price = oracle_prices.prices[token_idx].price;
-            let ref_price =
-                oracle_prices.prices[usize::from(oracle_mappings.ref_price[token_idx])].price;
-            let ref_price_tolerance_bps = oracle_mappings.get_ref_price_tolerance_bps(token_idx);
-            check_ref_price_difference(new_price, ref_price, ref_price_tolerance_bps)?;
-        }
-        _ => {}
+    // check that the price is close enough to the ref prices if there are ref prices
+    if price_update_result == PriceUpdateResult::Updated {
+        let new_price = oracle_prices.prices[token_idx].price;
+        check_entry_ref_price(&oracle_mappings, &oracle_prices, metadata, token, new_price)?;
     }
 
     Ok(())
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    handlers::handler_refresh_prices::{check_execution_ctx, refresh_tokens, RefreshList},
+    states::{OracleMappings, TokenMetadatas},
+    ScopeError, MAX_ENTRIES,
+};
+
+/// Refresh all the mapped entries tagged with `group_id`.
+///
+/// The remaining accounts are the price accounts (and their extra accounts) of the entries
+/// of the group, in increasing entry index order.
+pub fn refresh_price_group<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
+    group_id: u8,
+) -> Result<()> {
+    check_execution_ctx(
+        &ctx.accounts.instruction_sysvar_account_info,
+        &ctx.accounts.configuration.load()?,
+    )?;
+
+    let tokens = {
+        let oracle_mappings = ctx.accounts.oracle_mappings.load()?;
+        let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+        get_group_tokens(&oracle_mappings, &tokens_metadata, group_id)?
+    };
+    msg!("Refreshing group {group_id}: {tokens:?}");
+
+    refresh_tokens(&*ctx.accounts, ctx.remaining_accounts, &tokens)
+}
+
+/// List the entries of a group that can be refreshed with a refresh list instruction
+pub fn get_group_tokens(
+    oracle_mappings: &OracleMappings,
+    tokens_metadata: &TokenMetadatas,
+    group_id: u8,
+) -> Result<Vec<u16>> {
+    require_gt!(u64::BITS, u32::from(group_id), ScopeError::BadGroupId);
+    let group_mask = 1_u64 << group_id;
+
+    let tokens = (0..MAX_ENTRIES)
+        .filter(|&entry_id| {
+            tokens_metadata.metadatas_array[entry_id].group_ids_bitset & group_mask != 0
+                && oracle_mappings.is_entry_used(entry_id)
+                && oracle_mappings
+                    .get_entry_type(entry_id)
+                    .map(|price_type| price_type.is_refreshed_by_list())
+                    .unwrap_or(false)
+        })
+        .map(|entry_id| u16::try_from(entry_id).unwrap())
+        .collect();
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{events::error_code, oracles::OracleType};
+
+    #[test]
+    fn test_group_tokens() {
+        let mut oracle_mappings: Box<OracleMappings> = Box::new(bytemuck::Zeroable::zeroed());
+        let mut tokens_metadata: Box<TokenMetadatas> = Box::new(bytemuck::Zeroable::zeroed());
+        for entry_id in 0..MAX_ENTRIES {
+            oracle_mappings.reset_entry(entry_id);
+        }
+        for (entry_id, price_type) in [
+            (1, OracleType::PythPull),
+            (2, OracleType::Chainlink),
+            (3, OracleType::PythLazer),
+            (4, OracleType::PythPull),
+            (300, OracleType::SwitchboardOnDemand),
+        ] {
+            oracle_mappings.set_entry_mapping(
+                entry_id,
+                Some(Pubkey::new_unique()),
+                price_type,
+                [0; 20],
+            );
+        }
+        // Entry 5 is in the group but unmapped, entry 4 in another group
+        for (entry_id, group_ids_bitset) in [
+            (1, 1 << 7),
+            (2, 1 << 7),
+            (3, 1 << 7),
+            (4, 1 << 2),
+            (5, 1 << 7),
+            (300, 1 << 7 | 1),
+        ] {
+            tokens_metadata.metadatas_array[entry_id].group_ids_bitset = group_ids_bitset;
+        }
+
+        assert_eq!(
+            get_group_tokens(&oracle_mappings, &tokens_metadata, 7).unwrap(),
+            vec![1, 300]
+        );
+        assert_eq!(
+            get_group_tokens(&oracle_mappings, &tokens_metadata, 2).unwrap(),
+            vec![4]
+        );
+        assert!(get_group_tokens(&oracle_mappings, &tokens_metadata, 63)
+            .unwrap()
+            .is_empty());
+        let err = get_group_tokens(&oracle_mappings, &tokens_metadata, 64).unwrap_err();
+        assert_eq!(error_code(&err), Some(ScopeError::BadGroupId.into()));
+    }
+}
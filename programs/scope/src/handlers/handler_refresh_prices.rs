@@ -1,8 +1,10 @@
 use std::convert::TryInto;
 
 use anchor_lang::prelude::*;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use solana_program::{
     instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    program::set_return_data,
     pubkey,
     sysvar::instructions::{
         load_current_index_checked, load_instruction_at_checked, ID as SYSVAR_INSTRUCTIONS_ID,
@@ -10,14 +12,46 @@ use solana_program::{
 };
 
 use crate::{
-    oracles::{get_non_zero_price, OracleType},
-    states::{OracleMappings, OraclePrices, OracleTwaps},
-    utils::price_impl::check_ref_price_difference,
-    ScopeError,
+    events::{self, PriceSkipReason, PriceSkipped, PriceUpdated, TwapUpdateFailed},
+    oracles::{get_non_zero_price, skip_extra_accounts, OracleType},
+    states::{
+        Configuration, DatedPrice, OracleMappings, OraclePrices, OracleTwaps, TokenMetadata,
+        TokenMetadatas,
+    },
+    utils::{
+        dependencies::{get_price_sources, get_refresh_order},
+        price_impl::{check_entry_ref_price, check_price_move},
+    },
+    ScopeError, MAX_ENTRIES_U16,
 };
 
 const COMPUTE_BUDGET_ID: Pubkey = pubkey!("ComputeBudget111111111111111111111111111111");
 
+/// Outcome of the refresh of a single token.
+///
+/// Refresh instructions return one byte per requested token, in the order of the
+/// provided token list, through the instruction return data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum RefreshStatus {
+    Updated = 0,
+    SkippedUnmapped = 1,
+    ValidationFailed = 2,
+    RefCheckFailed = 3,
+    PriceMoveTooLarge = 4,
+    SkippedPaused = 5,
+}
+
+pub(crate) fn set_refresh_statuses_return_data(statuses: &[RefreshStatus]) {
+    let data: Vec<u8> = statuses.iter().map(|&status| status.into()).collect();
+    set_return_data(&data);
+}
+
+/// Accounts of the refresh of a list of tokens.
+///
+/// The configuration and the token metadatas follow the accounts of the original layout, so that
+/// callers of the original layout fail instead of refreshing without the pauses, the refresh
+/// allowlists and the checks configured in the token metadatas.
 #[derive(Accounts)]
 pub struct RefreshList<'info> {
     #[account(mut, has_one = oracle_mappings)]
@@ -28,6 +62,15 @@ pub struct RefreshList<'info> {
     /// CHECK: Sysvar fixed address
     #[account(address = SYSVAR_INSTRUCTIONS_ID)]
     pub instruction_sysvar_account_info: AccountInfo<'info>,
+    #[account(
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+    /// Per-entry refresh settings
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
     // Note: use remaining accounts as price accounts
 }
 
@@ -35,10 +78,35 @@ pub fn refresh_price_list<'info>(
     ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
     tokens: &[u16],
 ) -> Result<()> {
-    check_execution_ctx(&ctx.accounts.instruction_sysvar_account_info)?;
+    check_execution_ctx(
+        &ctx.accounts.instruction_sysvar_account_info,
+        &ctx.accounts.configuration.load()?,
+    )?;
 
-    let oracle_mappings = ctx.accounts.oracle_mappings.load()?;
-    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
+    refresh_tokens(&*ctx.accounts, ctx.remaining_accounts, tokens)
+}
+
+/// Refresh the given tokens, expecting `remaining_accounts` to be the price accounts
+/// (and their extra accounts) of the tokens in the same order.
+///
+/// Paused entries consume their price account and extra accounts without reading them.
+///
+/// Entries opted in with `rederive_on_source_refresh` are then recomputed if one of their
+/// sources was updated, see [`rederive_entries`]. The return data only holds the statuses of the
+/// requested tokens, the rederived entries are reported with the `PriceUpdated` and
+/// `PriceSkipped` events.
+pub(crate) fn refresh_tokens<'info>(
+    accounts: &RefreshList<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    tokens: &[u16],
+) -> Result<()> {
+    let configuration = accounts.configuration.load()?;
+    if configuration.feed_paused != 0 {
+        return err!(ScopeError::FeedPaused);
+    }
+    let oracle_mappings = accounts.oracle_mappings.load()?;
+    let mut oracle_twaps = accounts.oracle_twaps.load_mut()?;
+    let tokens_metadata = accounts.tokens_metadata.load()?;
 
     // No token to refresh
     if tokens.is_empty() {
@@ -50,7 +118,7 @@ pub fn refresh_price_list<'info>(
         return Err(ProgramError::InvalidArgument.into());
     }
     // Check the received token list is at least as long as the number of provided accounts
-    if tokens.len() > ctx.remaining_accounts.len() {
+    if tokens.len() > remaining_accounts.len() {
         return err!(ScopeError::AccountsAndTokenMismatch);
     }
 
@@ -59,7 +127,10 @@ pub fn refresh_price_list<'info>(
 
     let zero_pk: Pubkey = Pubkey::default();
 
-    let mut accounts_iter = ctx.remaining_accounts.iter();
+    let mut accounts_iter = remaining_accounts.iter();
+    let mut statuses = Vec::with_capacity(tokens.len());
+    // Updated entries in update order, to rederive the entries refreshed before a source
+    let mut updated_entries = Vec::with_capacity(tokens.len());
 
     for &token_nb in tokens.iter() {
         let token_idx: usize = token_nb.into();
@@ -76,6 +147,13 @@ pub fn refresh_price_list<'info>(
         // Ignore unset mapping accounts
         if zero_pk == *oracle_mapping {
             msg!("Skipping token {} as no mapping is set", token_idx);
+            emit!(PriceSkipped {
+                token: token_nb,
+                price_type,
+                reason: PriceSkipReason::NoMapping,
+                error_code: None,
+            });
+            statuses.push(RefreshStatus::SkippedUnmapped);
             continue;
         }
         // Check that the provided oracle accounts are the one referenced in oracleMapping
@@ -87,6 +165,26 @@ pub fn refresh_price_list<'info>(
             );
             return err!(ScopeError::UnexpectedAccount);
         }
+        let metadata = &tokens_metadata.metadatas_array[token_idx];
+        if configuration.is_entry_paused(token_idx, metadata.group_ids_bitset) {
+            if fail_tx_on_error {
+                return err!(ScopeError::EntryPaused);
+            }
+            msg!("Skipping token {} as it is paused", token_idx);
+            skip_extra_accounts(
+                &mut accounts_iter,
+                price_type,
+                &oracle_mappings.generic[token_idx],
+            );
+            emit!(PriceSkipped {
+                token: token_nb,
+                price_type,
+                reason: PriceSkipReason::Paused,
+                error_code: None,
+            });
+            statuses.push(RefreshStatus::SkippedPaused);
+            continue;
+        }
         let clock = Clock::get()?;
         let price_res = get_non_zero_price(
             price_type,
@@ -95,7 +193,8 @@ pub fn refresh_price_list<'info>(
             &clock,
             &oracle_twaps,
             &oracle_mappings,
-            &ctx.accounts.oracle_prices,
+            &accounts.oracle_prices,
+            metadata,
             token_idx,
         );
         let price = if fail_tx_on_error {
@@ -103,99 +202,532 @@ pub fn refresh_price_list<'info>(
         } else {
             match price_res {
                 Ok(price) => price,
-                Err(_) => {
+                Err(e) => {
                     msg!(
                         "Price skipped as validation failed (token {token_idx}, type {price_type:?})",
                     );
+                    emit!(PriceSkipped {
+                        token: token_nb,
+                        price_type,
+                        reason: PriceSkipReason::from_error(&e),
+                        error_code: events::error_code(&e),
+                    });
+                    statuses.push(RefreshStatus::ValidationFailed);
                     continue;
                 }
             }
         };
 
-        if oracle_mappings.is_twap_enabled(token_idx) {
-            if let Err(e) = crate::oracles::twap::update_twaps(
-                &mut oracle_twaps,
-                token_idx,
-                &price,
-                oracle_mappings.twap_enabled_bitmask[token_idx],
-            ) {
-                msg!("Error while updating TWAP of token {token_idx}: {e:?}",);
-            }
+        let status = check_and_store_price(
+            &accounts.oracle_prices,
+            &oracle_mappings,
+            &mut oracle_twaps,
+            metadata,
+            token_nb,
+            price_type,
+            price,
+            &clock,
+            fail_tx_on_error,
+        )?;
+        if status == RefreshStatus::Updated {
+            updated_entries.push(token_nb);
         }
+        statuses.push(status);
+    }
 
-        // Only temporary load as mut to allow prices to be computed based on a scope chain
-        // from the price feed that is currently updated
+    rederive_entries(
+        &accounts.oracle_prices,
+        &configuration,
+        &oracle_mappings,
+        &mut oracle_twaps,
+        &tokens_metadata.metadatas_array,
+        &mut updated_entries,
+    )?;
 
-        let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
+    set_refresh_statuses_return_data(&statuses);
 
-        // check that the price is close enough to the ref price if there is a ref price
-        if oracle_mappings.ref_price[token_idx] != u16::MAX {
-            let ref_price =
-                oracle_prices.prices[usize::from(oracle_mappings.ref_price[token_idx])].price;
+    Ok(())
+}
 
-            let ref_price_tolerance_bps = oracle_mappings.get_ref_price_tolerance_bps(token_idx);
-            if let Err(diff_err) =
-                check_ref_price_difference(price.price, ref_price, ref_price_tolerance_bps)
-            {
-                if fail_tx_on_error {
-                    return Err(diff_err);
-                } else {
-                    msg!(
-                        "Price skipped as ref price check failed (token {token_idx}, type {price_type:?})",
-                    );
-                    continue;
-                }
-            }
+/// Check a new `price` of the entry `token_nb` against its stored price and its ref prices,
+/// then update its TWAPs and store it.
+///
+/// Failed checks return the corresponding status, or fail if `fail_tx_on_error`.
+#[allow(clippy::too_many_arguments)]
+fn check_and_store_price(
+    oracle_prices: &AccountLoader<OraclePrices>,
+    oracle_mappings: &OracleMappings,
+    oracle_twaps: &mut OracleTwaps,
+    metadata: &TokenMetadata,
+    token_nb: u16,
+    price_type: OracleType,
+    price: DatedPrice,
+    clock: &Clock,
+    fail_tx_on_error: bool,
+) -> Result<RefreshStatus> {
+    let token_idx = usize::from(token_nb);
+
+    // check that the price did not move more than allowed since the stored one
+    let previous_price = oracle_prices.load()?.prices[token_idx];
+    if let Err(e) = check_price_move(
+        &previous_price,
+        &price,
+        metadata.max_price_move_bps,
+        metadata.max_price_move_period_s,
+        metadata.max_price_move_cap_bps,
+    ) {
+        if fail_tx_on_error {
+            return Err(e.into());
+        } else {
+            msg!(
+                "Price skipped as max price move check failed (token {token_idx}, type {price_type:?})",
+            );
+            emit!(PriceSkipped {
+                token: token_nb,
+                price_type,
+                reason: e.into(),
+                error_code: Some(e.into()),
+            });
+            return Ok(RefreshStatus::PriceMoveTooLarge);
         }
-        let to_update = oracle_prices
-            .prices
-            .get_mut(token_idx)
-            .ok_or(ScopeError::BadTokenNb)?;
+    }
 
-        msg!(
-            "tk {}, {:?}: {:?} to {:?} | prev_slot: {:?}, new_slot: {:?}, crt_slot: {:?}",
+    if oracle_mappings.is_twap_enabled(token_idx) {
+        if let Err(e) = crate::oracles::twap::update_twaps(
+            oracle_twaps,
             token_idx,
+            &price,
+            oracle_mappings.twap_enabled_bitmask[token_idx],
+        ) {
+            msg!("Error while updating TWAP of token {token_idx}: {e:?}",);
+            emit!(TwapUpdateFailed {
+                token: token_nb,
+                error_code: events::error_code(&e),
+            });
+        }
+    }
+
+    // Only temporary load as mut to allow prices to be computed based on a scope chain
+    // from the price feed that is currently updated
+
+    let mut oracle_prices = oracle_prices.load_mut()?;
+
+    // check that the price is close enough to the ref price if there is a ref price
+    if let Err(diff_err) = check_entry_ref_price(
+        oracle_mappings,
+        &oracle_prices,
+        metadata,
+        token_nb,
+        price.price,
+    ) {
+        if fail_tx_on_error {
+            return Err(diff_err);
+        } else {
+            msg!(
+                "Price skipped as ref price check failed (token {token_idx}, type {price_type:?})",
+            );
+            return Ok(RefreshStatus::RefCheckFailed);
+        }
+    }
+    let to_update = oracle_prices
+        .prices
+        .get_mut(token_idx)
+        .ok_or(ScopeError::BadTokenNb)?;
+
+    msg!(
+        "tk {}, {:?}: {:?} to {:?} | prev_slot: {:?}, new_slot: {:?}, crt_slot: {:?}",
+        token_idx,
+        price_type,
+        to_update.price.value,
+        price.price.value,
+        to_update.last_updated_slot,
+        price.last_updated_slot,
+        clock.slot,
+    );
+    emit!(PriceUpdated {
+        token: token_nb,
+        price_type,
+        old_price: to_update.price,
+        new_price: price.price,
+        old_slot: to_update.last_updated_slot,
+        new_slot: price.last_updated_slot,
+        unix_timestamp: price.unix_timestamp,
+        current_slot: clock.slot,
+    });
+
+    *to_update = price;
+    Ok(RefreshStatus::Updated)
+}
+
+/// Entries opted in with `rederive_on_source_refresh` that transitively depend on one of the
+/// `updated_entries`, in refresh order.
+///
+/// Only the flags of the metadatas are scanned, the mappings being read for the opted-in entries
+/// only: without any, nothing is allocated. The dependents are then searched among the opted-in
+/// entries from the `updated_entries` only.
+fn get_entries_to_rederive(
+    oracle_mappings: &OracleMappings,
+    metadatas: &[TokenMetadata],
+    updated_entries: &[u16],
+) -> Result<Vec<u16>> {
+    let derived_entries: Vec<(u16, Vec<u16>)> = (0..MAX_ENTRIES_U16)
+        .filter(|&entry| {
+            let entry_id = usize::from(entry);
+            metadatas[entry_id].rederives_on_source_refresh()
+                && oracle_mappings
+                    .get_entry_type(entry_id)
+                    .is_ok_and(OracleType::is_derived_without_accounts)
+        })
+        .map(|entry| (entry, get_price_sources(oracle_mappings, entry.into())))
+        .collect();
+    if derived_entries.is_empty() || updated_entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut to_rederive: Vec<u16> = Vec::new();
+    let mut changed_entries = updated_entries.to_vec();
+    while let Some(changed_entry) = changed_entries.pop() {
+        for (entry, sources) in derived_entries.iter() {
+            if sources.contains(&changed_entry) && !to_rederive.contains(entry) {
+                to_rederive.push(*entry);
+                changed_entries.push(*entry);
+            }
+        }
+    }
+    if to_rederive.is_empty() {
+        return Ok(to_rederive);
+    }
+    to_rederive.sort_unstable();
+
+    // The refresh order also holds the derived sources of the entries, not rederived
+    Ok(get_refresh_order(oracle_mappings, &to_rederive)?
+        .into_iter()
+        .filter(|entry| to_rederive.binary_search(entry).is_ok())
+        .collect())
+}
+
+/// Recompute, in dependency order, the entries opted in with `rederive_on_source_refresh` that
+/// have a source updated after them in this instruction.
+///
+/// `updated_entries` holds the entries updated in this instruction in update order, the
+/// rederived ones are appended to it. Failed recomputations are skipped and only logged.
+fn rederive_entries<'info>(
+    oracle_prices: &AccountLoader<'info, OraclePrices>,
+    configuration: &Configuration,
+    oracle_mappings: &OracleMappings,
+    oracle_twaps: &mut OracleTwaps,
+    metadatas: &[TokenMetadata],
+    updated_entries: &mut Vec<u16>,
+) -> Result<()> {
+    if updated_entries.is_empty() {
+        return Ok(());
+    }
+    let to_rederive = get_entries_to_rederive(oracle_mappings, metadatas, updated_entries)?;
+    if to_rederive.is_empty() {
+        return Ok(());
+    }
+
+    let clock = Clock::get()?;
+    // Derived types don't read their price account
+    let base_account = oracle_prices.to_account_info();
+
+    for entry in to_rederive {
+        let entry_id = usize::from(entry);
+        let metadata = &metadatas[entry_id];
+        if !has_source_updated_after(oracle_mappings, updated_entries, entry)
+            || configuration.is_entry_paused(entry_id, metadata.group_ids_bitset)
+        {
+            continue;
+        }
+
+        let price_type = oracle_mappings.get_entry_type(entry_id)?;
+        msg!("Rederiving token {entry_id} after the refresh of its sources");
+        let price = match get_non_zero_price(
             price_type,
-            to_update.price.value,
-            price.price.value,
-            to_update.last_updated_slot,
-            price.last_updated_slot,
-            clock.slot,
-        );
+            &base_account,
+            &mut std::iter::empty::<&AccountInfo>(),
+            &clock,
+            oracle_twaps,
+            oracle_mappings,
+            oracle_prices,
+            metadata,
+            entry_id,
+        ) {
+            Ok(price) => price,
+            Err(e) => {
+                msg!(
+                    "Rederived price skipped as validation failed (token {entry_id}, type {price_type:?})",
+                );
+                emit!(PriceSkipped {
+                    token: entry,
+                    price_type,
+                    reason: PriceSkipReason::from_error(&e),
+                    error_code: events::error_code(&e),
+                });
+                continue;
+            }
+        };
 
-        *to_update = price;
+        let status = check_and_store_price(
+            oracle_prices,
+            oracle_mappings,
+            oracle_twaps,
+            metadata,
+            entry,
+            price_type,
+            price,
+            &clock,
+            false,
+        )?;
+        if status == RefreshStatus::Updated {
+            updated_entries.push(entry);
+        }
     }
 
     Ok(())
 }
 
+/// Whether a source of `entry` was updated after its last update in `updated_entries`
+fn has_source_updated_after(
+    oracle_mappings: &OracleMappings,
+    updated_entries: &[u16],
+    entry: u16,
+) -> bool {
+    let last_update = |entry: u16| updated_entries.iter().rposition(|&e| e == entry);
+    let entry_update = last_update(entry);
+    get_price_sources(oracle_mappings, entry.into())
+        .into_iter()
+        .any(|source| last_update(source) > entry_update)
+}
+
 /// Ensure that the refresh instruction is executed directly to avoid any manipulation:
 ///
 /// - Check that the current instruction is executed by our program id (not in CPI).
 /// - Check that instructions preceding the refresh are compute budget instructions.
-fn check_execution_ctx(instruction_sysvar_account_info: &AccountInfo) -> Result<()> {
+///
+/// The programs allowlisted in the `configuration` are allowed on top of these.
+pub(crate) fn check_execution_ctx(
+    instruction_sysvar_account_info: &AccountInfo,
+    configuration: &Configuration,
+) -> Result<()> {
     let current_index: usize = load_current_index_checked(instruction_sysvar_account_info)?.into();
 
-    // 1- Check that the current instruction is executed by our program id (not in CPI).
+    // 1- Check that the current instruction is executed by our program id (not in CPI),
+    // or directly by an allowlisted program.
     let current_ix = load_instruction_at_checked(current_index, instruction_sysvar_account_info)?;
 
-    // the current ix must be executed by our program id. otherwise, it's a CPI.
     if crate::ID != current_ix.program_id {
+        // This is a CPI, only allowed if the top-level program is allowlisted and calls us directly.
+        if !configuration.is_refresh_cpi_allowed(&current_ix.program_id)
+            || get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT + 1
+        {
+            msg!(
+                "Refresh in CPI from {} is not allowed",
+                current_ix.program_id
+            );
+            return err!(ScopeError::RefreshInCPI);
+        }
+    } else if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        // The current stack height must be the initial one. Otherwise, it's a CPI.
         return err!(ScopeError::RefreshInCPI);
     }
 
-    // The current stack height must be the initial one. Otherwise, it's a CPI.
-    if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
-        return err!(ScopeError::RefreshInCPI);
-    }
-
-    // 2- Check that instructions preceding the refresh are compute budget instructions.
+    // 2- Check that instructions preceding the refresh are compute budget instructions
+    // or instructions of allowlisted programs.
     for ixn in 0..current_index {
         let ix = load_instruction_at_checked(ixn, instruction_sysvar_account_info)?;
-        if ix.program_id != COMPUTE_BUDGET_ID {
+        if ix.program_id != COMPUTE_BUDGET_ID
+            && !configuration.is_refresh_preceding_ix_allowed(&ix.program_id)
+        {
+            msg!(
+                "Unexpected instruction {} preceding the refresh",
+                ix.program_id
+            );
             return err!(ScopeError::RefreshWithUnexpectedIxs);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::*;
+    use crate::{
+        events::error_code,
+        states::Price,
+        utils::test_utils::{
+            new_feed, set_most_recent_of_entry, set_pyth_entry, set_twap_entry, TestAccount,
+        },
+    };
+
+    /// Pyth entries 0 and 1, and derived entries:
+    /// - 2 the most recent of 0, rederived
+    /// - 3 the TWAP of 2, rederived
+    /// - 4 the most recent of 1 and 3, rederived
+    /// - 5 the most recent of 0, not rederived
+    /// - 6 the most recent of 5, rederived
+    fn rederived_feed() -> (Box<OracleMappings>, Vec<TokenMetadata>) {
+        let (mut oracle_mappings, mut metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_pyth_entry(&mut oracle_mappings, 1);
+        for (entry_id, sources) in [(2, &[0][..]), (4, &[1, 3]), (5, &[0]), (6, &[5])] {
+            set_most_recent_of_entry(&mut oracle_mappings, entry_id, sources);
+        }
+        set_twap_entry(&mut oracle_mappings, 3, 2);
+        for entry_id in [2, 3, 4, 6] {
+            metadatas[entry_id].rederive_on_source_refresh = 1;
+        }
+        (oracle_mappings, metadatas)
+    }
+
+    #[test]
+    fn test_refresh_status_bytes() {
+        let statuses = [
+            RefreshStatus::Updated,
+            RefreshStatus::SkippedUnmapped,
+            RefreshStatus::ValidationFailed,
+            RefreshStatus::RefCheckFailed,
+            RefreshStatus::PriceMoveTooLarge,
+            RefreshStatus::SkippedPaused,
+        ];
+        for (byte, status) in statuses.into_iter().enumerate() {
+            let byte = u8::try_from(byte).unwrap();
+            assert_eq!(u8::from(status), byte);
+            assert_eq!(RefreshStatus::try_from(byte).unwrap(), status);
+        }
+        assert!(RefreshStatus::try_from(6).is_err());
+    }
+
+    #[test]
+    fn test_entries_to_rederive() {
+        let (oracle_mappings, metadatas) = rederived_feed();
+        let to_rederive = |updated_entries: &[u16]| -> Vec<u16> {
+            get_entries_to_rederive(&oracle_mappings, &metadatas, updated_entries).unwrap()
+        };
+
+        // Transitive dependents, after their sources, skipping the entries not opted in
+        assert_eq!(to_rederive(&[0]), vec![2, 3, 4]);
+        assert_eq!(to_rederive(&[1]), vec![4]);
+        assert_eq!(to_rederive(&[3]), vec![4]);
+        // Entries without opted-in dependents
+        assert!(to_rederive(&[4]).is_empty());
+        // 6 follows its refreshed source even if that one is not rederived
+        assert_eq!(to_rederive(&[5]), vec![6]);
+        assert!(to_rederive(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_no_entries_to_rederive_without_opted_in_entries() {
+        let (oracle_mappings, mut metadatas) = rederived_feed();
+        for metadata in metadatas.iter_mut() {
+            metadata.rederive_on_source_refresh = 0;
+        }
+        assert!(
+            get_entries_to_rederive(&oracle_mappings, &metadatas, &[0, 1, 2])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_source_updated_after() {
+        let (oracle_mappings, _) = rederived_feed();
+
+        assert!(has_source_updated_after(&oracle_mappings, &[0], 2));
+        assert!(has_source_updated_after(&oracle_mappings, &[2, 0], 2));
+        assert!(!has_source_updated_after(&oracle_mappings, &[0, 2], 2));
+        assert!(has_source_updated_after(&oracle_mappings, &[0, 2, 0], 2));
+        assert!(!has_source_updated_after(&oracle_mappings, &[1], 2));
+        // Any of the sources
+        assert!(has_source_updated_after(&oracle_mappings, &[3, 4, 1], 4));
+    }
+
+    #[test]
+    fn test_original_accounts_layout_is_refused() {
+        let mut instruction_sysvar = TestAccount::new(solana_program::sysvar::ID, Vec::new());
+        instruction_sysvar.key = SYSVAR_INSTRUCTIONS_ID;
+        let mut accounts = [
+            TestAccount::zero_copy::<OraclePrices>(),
+            TestAccount::zero_copy::<OracleMappings>(),
+            TestAccount::zero_copy::<OracleTwaps>(),
+            instruction_sysvar,
+        ];
+        let account_infos: Vec<AccountInfo> =
+            accounts.iter_mut().map(TestAccount::account_info).collect();
+
+        // Without the configuration and the token metadatas the safeguards cannot be applied
+        let err = RefreshList::try_accounts(
+            &crate::ID,
+            &mut &account_infos[..],
+            &[],
+            &mut BTreeMap::new(),
+            &mut BTreeSet::new(),
+        )
+        .map(|_| ())
+        .unwrap_err();
+        assert_eq!(
+            error_code(&err),
+            Some(ErrorCode::AccountNotEnoughKeys.into())
+        );
+    }
+
+    #[test]
+    fn test_capped_entry_is_not_written() {
+        let mut oracle_prices_account = TestAccount::zero_copy::<OraclePrices>();
+        let oracle_prices_info = oracle_prices_account.account_info();
+        let oracle_prices = AccountLoader::<OraclePrices>::try_from(&oracle_prices_info).unwrap();
+        let (mut oracle_mappings, _) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        let mut oracle_twaps: Box<OracleTwaps> = Box::new(bytemuck::Zeroable::zeroed());
+        let clock = Clock::default();
+        let dated_price = |value: u64, exp: u64, unix_timestamp: u64| DatedPrice {
+            price: Price { value, exp },
+            last_updated_slot: unix_timestamp,
+            unix_timestamp,
+            ..Default::default()
+        };
+        let stored_price = dated_price(100, 0, 100);
+        oracle_prices.load_mut().unwrap().prices[0] = stored_price;
+        // At most 1% per minute
+        let metadata = TokenMetadata {
+            max_price_move_bps: 100,
+            max_price_move_period_s: 60,
+            ..Default::default()
+        };
+        let mut store = |price: DatedPrice, fail_tx_on_error: bool| {
+            check_and_store_price(
+                &oracle_prices,
+                &oracle_mappings,
+                &mut oracle_twaps,
+                &metadata,
+                0,
+                OracleType::PythPull,
+                price,
+                &clock,
+                fail_tx_on_error,
+            )
+        };
+
+        // +10% after 10s is skipped, or fails the transaction, and the price is kept
+        assert_eq!(
+            store(dated_price(110, 0, 110), false).unwrap(),
+            RefreshStatus::PriceMoveTooLarge
+        );
+        let err = store(dated_price(110, 0, 110), true).unwrap_err();
+        assert_eq!(error_code(&err), Some(ScopeError::PriceMoveTooLarge.into()));
+        assert_eq!(oracle_prices.load().unwrap().prices[0], stored_price);
+
+        // +0.5% is within the cap
+        assert_eq!(
+            store(dated_price(1005, 1, 110), false).unwrap(),
+            RefreshStatus::Updated
+        );
+        assert_eq!(
+            oracle_prices.load().unwrap().prices[0],
+            dated_price(1005, 1, 110)
+        );
+    }
+}
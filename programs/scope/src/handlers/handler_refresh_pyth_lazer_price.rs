@@ -9,9 +9,11 @@ use solana_program::{
 };
 
 use crate::{
+    events::{self, PriceSkipReason, PriceSkipped, PriceUpdated, TwapUpdateFailed},
+    handlers::handler_refresh_prices::{set_refresh_statuses_return_data, RefreshStatus},
     oracles::{pyth_lazer, OracleType},
-    states::{OracleMappings, OraclePrices, OracleTwaps},
-    utils::price_impl::check_ref_price_difference,
+    states::{Configuration, OracleMappings, OraclePrices, OracleTwaps, TokenMetadatas},
+    utils::price_impl::check_entry_ref_price,
     ScopeError,
 };
 
@@ -47,6 +49,18 @@ pub struct RefreshPythLazerPrice<'info> {
     /// CHECK: Sysvar fixed address
     #[account(address = SYSVAR_INSTRUCTIONS_ID)]
     pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Holds the pause state of the feed
+    #[account(
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Per-entry refresh settings
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
 }
 
 pub fn refresh_pyth_lazer_price<'info>(
@@ -55,6 +69,12 @@ pub fn refresh_pyth_lazer_price<'info>(
     serialized_pyth_message: Vec<u8>,
     ed25519_instruction_index: u16,
 ) -> Result<()> {
+    let configuration = ctx.accounts.configuration.load()?;
+    if configuration.feed_paused != 0 {
+        return err!(ScopeError::FeedPaused);
+    }
+    let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+
     // 1 - verify and deserialize the pyth message
     let verify_ix = create_pyth_lazer_verify_ix(
         ctx.accounts,
@@ -83,6 +103,7 @@ pub fn refresh_pyth_lazer_price<'info>(
     pyth_lazer::validate_payload_data_for_group(&payload_data, tokens.len())?;
     let oracle_mappings = ctx.accounts.oracle_mappings.load()?;
     let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
+    let mut statuses = Vec::with_capacity(tokens.len());
 
     for (i, &token) in tokens.iter().enumerate() {
         let token_idx: usize = token.into();
@@ -102,6 +123,19 @@ pub fn refresh_pyth_lazer_price<'info>(
             ScopeError::BadTokenType
         );
 
+        let metadata = &tokens_metadata.metadatas_array[token_idx];
+        if configuration.is_entry_paused(token_idx, metadata.group_ids_bitset) {
+            msg!("Skipping token {} as it is paused", token_idx);
+            emit!(PriceSkipped {
+                token,
+                price_type,
+                reason: PriceSkipReason::Paused,
+                error_code: None,
+            });
+            statuses.push(RefreshStatus::SkippedPaused);
+            continue;
+        }
+
         {
             let dated_price_ref = &mut oracle_prices.prices[token_idx];
             let old_price = *dated_price_ref;
@@ -121,6 +155,13 @@ pub fn refresh_pyth_lazer_price<'info>(
                         "Price skipped as validation failed for token {token_idx}: {:?}",
                         e
                     );
+                    emit!(PriceSkipped {
+                        token,
+                        price_type,
+                        reason: e.into(),
+                        error_code: Some(e.into()),
+                    });
+                    statuses.push(RefreshStatus::ValidationFailed);
                     continue;
                 }
             }
@@ -135,6 +176,16 @@ pub fn refresh_pyth_lazer_price<'info>(
                 dated_price_ref.last_updated_slot,
                 clock.slot,
             );
+            emit!(PriceUpdated {
+                token,
+                price_type,
+                old_price: old_price.price,
+                new_price: dated_price_ref.price,
+                old_slot: old_price.last_updated_slot,
+                new_slot: dated_price_ref.last_updated_slot,
+                unix_timestamp: dated_price_ref.unix_timestamp,
+                current_slot: clock.slot,
+            });
 
             if oracle_mappings.is_twap_enabled(token_idx) {
                 let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
@@ -145,20 +196,22 @@ pub fn refresh_pyth_lazer_price<'info>(
                     oracle_mappings.twap_enabled_bitmask[token_idx],
                 ) {
                     msg!("Error while updating TWAP of token {token_idx}: {e:?}",);
+                    emit!(TwapUpdateFailed {
+                        token,
+                        error_code: events::error_code(&e),
+                    });
                 }
             }
         }
 
-        // check that the price is close enough to the ref price if there is a ref price
-        if oracle_mappings.ref_price[token_idx] != u16::MAX {
-            let new_price = oracle_prices.prices[token_idx].price;
-            let ref_price =
-                oracle_prices.prices[usize::from(oracle_mappings.ref_price[token_idx])].price;
-            let ref_price_tolerance_bps = oracle_mappings.get_ref_price_tolerance_bps(token_idx);
-            check_ref_price_difference(new_price, ref_price, ref_price_tolerance_bps)?;
-        }
+        // check that the price is close enough to the ref prices if there are ref prices
+        let new_price = oracle_prices.prices[token_idx].price;
+        check_entry_ref_price(&oracle_mappings, &oracle_prices, metadata, token, new_price)?;
+        statuses.push(RefreshStatus::Updated);
     }
 
+    set_refresh_statuses_return_data(&statuses);
+
     Ok(())
 }
 
@@ -1,15 +1,21 @@
 use anchor_lang::prelude::*;
 use solana_program::sysvar::instructions::ID as SYSVAR_INSTRUCTIONS_ID;
 
-use crate::{oracles::check_context, states::OracleTwaps, utils::pdas::seeds};
+use crate::{
+    oracles::check_context,
+    states::{OracleTwaps, Role},
+    utils::pdas::seeds,
+    ScopeError,
+};
 
 #[derive(Accounts)]
 #[instruction(token:u64, feed_name: String)]
 pub struct ResetTwap<'info> {
+    /// Admin or TWAP operator
     pub admin: Signer<'info>,
 
     #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump,
-        has_one = admin,
+        constraint = configuration.load()?.has_role(Role::TwapOperator, admin.key) @ ScopeError::MissingRole,
         has_one = oracle_twaps,
     )]
     pub configuration: AccountLoader<'info, crate::states::configuration::Configuration>,
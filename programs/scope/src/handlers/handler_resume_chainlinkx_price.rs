@@ -5,7 +5,7 @@ use crate::{
         chainlink::{ChainlinkXPriceData, GenericDataConvertible},
         check_context, OracleType,
     },
-    states::{Configuration, OracleMappings, OraclePrices, TokenMetadatas},
+    states::{Configuration, OracleMappings, OraclePrices, Role, TokenMetadatas},
     utils::pdas::seeds,
     ScopeError,
 };
@@ -13,9 +13,17 @@ use crate::{
 #[derive(Accounts)]
 #[instruction(token: u16, feed_name: String)]
 pub struct ResumeChainlinkXPrice<'info> {
+    /// Admin or Chainlink resume operator
     pub admin: Signer<'info>,
 
-    #[account(seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin, has_one = oracle_prices, has_one = oracle_mappings, has_one = tokens_metadata)]
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        constraint = configuration.load()?.has_role(Role::ChainlinkResumeOperator, admin.key) @ ScopeError::MissingRole,
+        has_one = oracle_prices,
+        has_one = oracle_mappings,
+        has_one = tokens_metadata,
+    )]
     pub configuration: AccountLoader<'info, Configuration>,
 
     #[account(mut, has_one = oracle_mappings)]
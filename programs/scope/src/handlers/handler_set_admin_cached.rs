@@ -1,6 +1,9 @@
 use anchor_lang::{prelude::*, Accounts};
 
-use crate::oracles::check_context;
+use crate::{
+    oracles::check_context,
+    states::{audit_log::get_audit_log, AuditLog, AuditRecordKind, AUDIT_NO_ENTRY},
+};
 
 #[derive(Accounts)]
 #[instruction(new_admin: Pubkey, feed_name: String)]
@@ -9,6 +12,10 @@ pub struct SetAdminCached<'info> {
 
     #[account(mut, seeds = [b"conf", feed_name.as_bytes()], bump, has_one = admin)]
     pub configuration: AccountLoader<'info, crate::states::configuration::Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
 }
 
 pub fn process(ctx: Context<SetAdminCached>, new_admin: Pubkey, feed_name: String) -> Result<()> {
@@ -22,6 +29,17 @@ pub fn process(ctx: Context<SetAdminCached>, new_admin: Pubkey, feed_name: Strin
 
     let configuration = &mut ctx.accounts.configuration.load_mut()?;
 
+    if let Some(audit_log) = get_audit_log(configuration, &ctx.accounts.audit_log)? {
+        audit_log.load_mut()?.push(
+            Clock::get()?.slot,
+            ctx.accounts.admin.key(),
+            AUDIT_NO_ENTRY,
+            AuditRecordKind::SetAdminCached,
+            configuration.admin_cached.as_ref(),
+            new_admin.as_ref(),
+        );
+    }
+
     configuration.admin_cached = new_admin;
 
     Ok(())
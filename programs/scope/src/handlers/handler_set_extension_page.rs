@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    states::{
+        audit_log::record_change, AuditLog, AuditRecordKind, Configuration, AUDIT_NO_ENTRY,
+        MAX_EXTENSION_PAGES,
+    },
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String, extension_feed_name: String)]
+pub struct SetExtensionPage<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Configuration of the feed holding the entries of the page
+    #[account(mut, seeds = [seeds::CONFIG, extension_feed_name.as_bytes()], bump, has_one = admin)]
+    pub extension_configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the base feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(ctx: Context<SetExtensionPage>, page: u8, linked: bool) -> Result<()> {
+    check_context(&ctx)?;
+
+    let page_idx = usize::from(page);
+    if page_idx == 0 || page_idx > MAX_EXTENSION_PAGES {
+        msg!("Page must be in [1, {}]", MAX_EXTENSION_PAGES);
+        return err!(ScopeError::InvalidExtensionPage);
+    }
+    let base_pk = ctx.accounts.configuration.key();
+    let extension_pk = ctx.accounts.extension_configuration.key();
+    require_keys_neq!(base_pk, extension_pk, ScopeError::InvalidExtensionPage);
+
+    let mut base = ctx.accounts.configuration.load_mut()?;
+    let mut extension = ctx.accounts.extension_configuration.load_mut()?;
+
+    // Pages are only one level deep
+    if base.is_extension_page() || extension.has_extension_pages() {
+        return err!(ScopeError::InvalidExtensionPage);
+    }
+
+    let slot = page_idx - 1;
+    let old_page = base.extension_pages[slot];
+    if linked {
+        if base.extension_pages[slot] != Pubkey::default() || extension.is_extension_page() {
+            msg!("Page {} or the extension feed is already linked", page);
+            return err!(ScopeError::InvalidExtensionPage);
+        }
+        msg!("Linking {} as page {} of {}", extension_pk, page, base_pk);
+        base.extension_pages[slot] = extension_pk;
+        base.extension_oracle_prices[slot] = extension.oracle_prices;
+        extension.base_configuration = base_pk;
+        extension.page_index = page;
+    } else {
+        if base.extension_pages[slot] != extension_pk {
+            msg!("{} is not page {} of {}", extension_pk, page, base_pk);
+            return err!(ScopeError::InvalidExtensionPage);
+        }
+        msg!(
+            "Unlinking {} from page {} of {}",
+            extension_pk,
+            page,
+            base_pk
+        );
+        base.extension_pages[slot] = Pubkey::default();
+        base.extension_oracle_prices[slot] = Pubkey::default();
+        extension.base_configuration = Pubkey::default();
+        extension.page_index = 0;
+    }
+
+    record_change(
+        &base,
+        &ctx.accounts.audit_log,
+        ctx.accounts.admin.key(),
+        AUDIT_NO_ENTRY,
+        AuditRecordKind::SetExtensionPage,
+        &[&[page][..], old_page.as_ref()].concat(),
+        &[&[page][..], base.extension_pages[slot].as_ref()].concat(),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    states::{audit_log::record_change, AuditLog, AuditRecordKind, Configuration, AUDIT_NO_ENTRY},
+    utils::pdas::seeds,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetMappingUpdateDelay<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(ctx: Context<SetMappingUpdateDelay>, delay_s: u64) -> Result<()> {
+    check_context(&ctx)?;
+
+    let now = u64::try_from(Clock::get()?.unix_timestamp).unwrap();
+    let configuration = &mut ctx.accounts.configuration.load_mut()?;
+
+    let old_delay_s = configuration.get_mapping_update_delay_s(now);
+
+    msg!(
+        "Setting mapping update delay from {}s to {}s",
+        old_delay_s,
+        delay_s
+    );
+    configuration.set_mapping_update_delay_s(now, delay_s);
+    record_change(
+        configuration,
+        &ctx.accounts.audit_log,
+        ctx.accounts.admin.key(),
+        AUDIT_NO_ENTRY,
+        AuditRecordKind::SetMappingUpdateDelay,
+        &old_delay_s.to_le_bytes(),
+        &delay_s.to_le_bytes(),
+    )?;
+    if configuration.next_mapping_update_delay_ts != 0 {
+        msg!(
+            "Shorter delay will take effect at {}",
+            configuration.next_mapping_update_delay_ts
+        );
+    }
+
+    Ok(())
+}
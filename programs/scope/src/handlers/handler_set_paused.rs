@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::PauseUpdated,
+    oracles::check_context,
+    states::{
+        audit_log::record_change, AuditLog, AuditRecordKind, Configuration, PauseTarget, Role,
+        AUDIT_NO_ENTRY,
+    },
+    utils::pdas::seeds,
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetPaused<'info> {
+    /// Admin or pauser
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        constraint = configuration.load()?.has_role(Role::Pauser, admin.key) @ ScopeError::MissingRole,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(ctx: Context<SetPaused>, target: PauseTarget, paused: bool) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!("Setting paused of {:?} to {}", target, paused);
+
+    let configuration = &mut ctx.accounts.configuration.load_mut()?;
+    let was_paused = configuration.is_paused(target);
+    configuration.set_paused(target, paused)?;
+
+    let entry_id = match target {
+        PauseTarget::Entry(entry_id) => entry_id,
+        PauseTarget::Feed | PauseTarget::Group(_) => AUDIT_NO_ENTRY,
+    };
+    record_change(
+        configuration,
+        &ctx.accounts.audit_log,
+        ctx.accounts.admin.key(),
+        entry_id,
+        AuditRecordKind::SetPaused,
+        &(target, was_paused).try_to_vec()?,
+        &(target, paused).try_to_vec()?,
+    )?;
+
+    emit!(PauseUpdated {
+        configuration: ctx.accounts.configuration.key(),
+        target,
+        paused,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    oracles::check_context,
+    states::{
+        audit_log::record_change, AuditLog, AuditRecordKind, Configuration, Role, AUDIT_NO_ENTRY,
+    },
+    utils::pdas::seeds,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SetRole<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, seeds = [seeds::CONFIG, feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(ctx: Context<SetRole>, role: Role, holder: Pubkey) -> Result<()> {
+    check_context(&ctx)?;
+
+    let configuration = &mut ctx.accounts.configuration.load_mut()?;
+    let old_holder = *configuration.get_role(role);
+
+    msg!("Setting {:?} role from {} to {}", role, old_holder, holder);
+
+    record_change(
+        configuration,
+        &ctx.accounts.audit_log,
+        ctx.accounts.admin.key(),
+        AUDIT_NO_ENTRY,
+        AuditRecordKind::SetRole,
+        &(role, old_holder).try_to_vec()?,
+        &(role, holder).try_to_vec()?,
+    )?;
+
+    *configuration.get_role_mut(role) = holder;
+
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+
+use crate::{
+    oracles::{get_non_zero_price_with_generic_data, validate_oracle_cfg, OracleType},
+    states::{Configuration, OracleMappings, OraclePrices, OracleTwaps, TokenMetadatas},
+    utils::{maybe_account, pdas::seeds},
+    ScopeError, MAX_ENTRIES_U16,
+};
+
+/// Handler expects as remaining accounts the proposed `price_info` (`crate::ID` when the
+/// oracle type does not use one) followed by the extra accounts needed to compute the price
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct SimulateMappingEntry<'info> {
+    #[account(
+        seeds = [seeds::CONFIG, feed_name.as_bytes()],
+        bump,
+        has_one = oracle_mappings,
+        has_one = oracle_prices,
+        has_one = oracle_twaps,
+        has_one = tokens_metadata,
+    )]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    pub oracle_mappings: AccountLoader<'info, OracleMappings>,
+    pub oracle_prices: AccountLoader<'info, OraclePrices>,
+    pub oracle_twaps: AccountLoader<'info, OracleTwaps>,
+    pub tokens_metadata: AccountLoader<'info, TokenMetadatas>,
+}
+
+/// Validate the proposed configuration of `entry_id` and compute its price, without writing
+/// anything. The resulting `DatedPrice` is returned through the return data.
+pub fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, SimulateMappingEntry<'info>>,
+    entry_id: u16,
+    price_type: OracleType,
+    generic_data: [u8; 20],
+) -> Result<()> {
+    require_gt!(MAX_ENTRIES_U16, entry_id, ScopeError::BadTokenNb);
+    if !can_simulate(price_type) {
+        msg!("Oracle type {price_type:?} cannot be simulated");
+        return err!(ScopeError::BadTokenType);
+    }
+
+    let mut accounts_iter = ctx.remaining_accounts.iter();
+    let price_info = accounts_iter
+        .next()
+        .ok_or(ScopeError::MissingPriceAccount)?;
+
+    let clock = Clock::get()?;
+    validate_oracle_cfg(price_type, maybe_account(price_info), &generic_data, &clock)?;
+
+    let entry_idx = usize::from(entry_id);
+    let oracle_mappings = ctx.accounts.oracle_mappings.load()?;
+    let oracle_twaps = ctx.accounts.oracle_twaps.load()?;
+    let tokens_metadata = ctx.accounts.tokens_metadata.load()?;
+
+    // The stored price of the entry is only comparable with the simulated one when they come
+    // from the same oracle type and price account
+    let same_source = oracle_mappings.is_entry_used(entry_idx)
+        && oracle_mappings.get_entry_type(entry_idx)? == price_type
+        && oracle_mappings.get_entry_mapping_pk(entry_idx)
+            == maybe_account(price_info).map(|a| a.key());
+
+    let price = get_non_zero_price_with_generic_data(
+        price_type,
+        price_info,
+        &mut accounts_iter,
+        &clock,
+        &oracle_twaps,
+        &oracle_mappings,
+        &ctx.accounts.oracle_prices,
+        &tokens_metadata.metadatas_array[entry_idx],
+        &generic_data,
+        entry_idx,
+        same_source,
+    )?;
+
+    msg!(
+        "Simulated entry {entry_id} ({price_type:?}): {:?} | slot: {}, ts: {}",
+        price.price,
+        price.last_updated_slot,
+        price.unix_timestamp,
+    );
+    set_return_data(bytemuck::bytes_of(&price));
+
+    Ok(())
+}
+
+/// Whether the price of a mapping of type `price_type` can be computed before it is applied
+fn can_simulate(price_type: OracleType) -> bool {
+    match price_type {
+        // TWAPs are validated through a different path and depend on the TWAP source
+        OracleType::ScopeTwap1h
+        | OracleType::ScopeTwap8h
+        | OracleType::ScopeTwap24h
+        | OracleType::Unused
+        | OracleType::DeprecatedPlaceholder1
+        | OracleType::DeprecatedPlaceholder2
+        | OracleType::DeprecatedPlaceholder3
+        | OracleType::DeprecatedPlaceholder4
+        | OracleType::DeprecatedPlaceholder5
+        | OracleType::DeprecatedPlaceholder6
+        | OracleType::DeprecatedPlaceholder7 => false,
+        _ => price_type.is_refreshed_by_list(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_simulate() {
+        for price_type in [
+            OracleType::PythPull,
+            OracleType::FixedPrice,
+            OracleType::MostRecentOf,
+            OracleType::MedianOf,
+        ] {
+            assert!(can_simulate(price_type), "{price_type:?}");
+        }
+        for price_type in [
+            OracleType::ScopeTwap1h,
+            OracleType::Unused,
+            OracleType::DeprecatedPlaceholder1,
+            OracleType::Chainlink,
+            OracleType::PythLazer,
+        ] {
+            assert!(!can_simulate(price_type), "{price_type:?}");
+        }
+    }
+}
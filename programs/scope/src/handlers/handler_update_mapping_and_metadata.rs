@@ -1,18 +1,34 @@
+use std::collections::BTreeMap;
+
 use anchor_lang::prelude::*;
 
 use crate::{
-    oracles::{update_generic_data_must_reset_price, validate_oracle_cfg, OracleType},
+    oracles::{
+        get_composite_source_entries, update_generic_data_must_reset_price, validate_oracle_cfg,
+        OracleType,
+    },
     states::{
-        Configuration, EmaType, OracleMappings, OraclePrices, OracleTwaps, TokenMetadata,
-        TokenMetadatas, TwapEnabledBitmask,
+        audit_log::split_audit_log, token_metadatas::MAX_ADDITIONAL_REF_PRICES, AuditLog,
+        AuditRecordKind, Configuration, EmaType, OracleMappings, OraclePrices, OracleTwaps, Role,
+        StaleRefPricePolicy, TokenMetadata, TokenMetadatas, TwapEnabledBitmask,
+    },
+    utils::{
+        consts::{FULL_BPS, MIN_PRICE_MOVE_CAP_BPS},
+        dependencies::{
+            check_dependents, check_no_dependency_cycle, EntryDependencies, Feed, FeedEntries,
+            UpdatedFeed,
+        },
+        list_set_bit_positions, maybe_account,
+        pdas::seeds,
     },
-    utils::{list_set_bit_positions, maybe_account, pdas::seeds},
     ScopeError, MAX_ENTRIES, MAX_ENTRIES_U16,
 };
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub enum UpdateOracleMappingAndMetadataEntry {
     /// This will remove the entry from the mapping and reset the price entry
+    ///
+    /// Refused if other entries still reference it (as price source or ref price) after the batch
     RemoveEntry,
     /// Updating the mapping config requires to provide the mapping account to the instruction.
     ///
@@ -20,6 +36,9 @@ pub enum UpdateOracleMappingAndMetadataEntry {
     /// - The price type is changed
     /// - The price info account is changed
     /// - The generic data is changed and the type requires it
+    ///
+    /// Changing the type of an entry other entries depend on requires to update them in the
+    /// same batch.
     MappingConfig {
         price_type: OracleType,
         generic_data: [u8; 20],
@@ -38,6 +57,129 @@ pub enum UpdateOracleMappingAndMetadataEntry {
     MetadataName(String),
     MetadataMaxPriceAgeSlots(u64),
     MetadataGroupIdsBitset(u64),
+    /// Reject refreshes moving the price by more than `max_move_bps` for each started
+    /// `period_s` elapsed since the stored price, and by more than `max_move_cap_bps` in any case.
+    /// The cap must be at least `max_move_bps` and `MIN_PRICE_MOVE_CAP_BPS`. A larger move keeps
+    /// the entry stale until the check is disabled, by setting `max_move_bps` to 0.
+    MetadataMaxPriceMove {
+        max_move_bps: u16,
+        period_s: u32,
+        max_move_cap_bps: u16,
+    },
+    /// Maximum confidence interval accepted from the oracle, in bps of the price.
+    /// Only used by `PythPull`, `PythPullEMA` and `SwitchboardOnDemand`; 0 restores the default.
+    MetadataConfidenceBps(u16),
+    /// Reject refreshes whose oracle price is older than the given number of seconds.
+    /// 0 disables the check.
+    MetadataMaxSourceAge(u64),
+    /// Reference prices checked on top of the mapping ref price (at most
+    /// `MAX_ADDITIONAL_REF_PRICES`, distinct from each other and from the mapping ref price), and
+    /// the number of references a new price must agree with (0 requires all of them). The
+    /// tolerance is the one of the mapping ref price. Checked by `refresh_price_list`,
+    /// `refresh_price_group`, `refresh_chainlink_price` and `refresh_pyth_lazer_price`.
+    MetadataAdditionalRefPrices {
+        ref_price_indexes: Vec<u16>,
+        quorum: u8,
+    },
+    /// Maximum age of the reference prices (0 disables the check) and what to do with the stale
+    /// ones. Checked with the ref prices, by `refresh_price_list`, `refresh_price_group`,
+    /// `refresh_chainlink_price` and `refresh_pyth_lazer_price`.
+    MetadataRefPriceMaxAge {
+        max_age_s: u32,
+        stale_policy: StaleRefPricePolicy,
+    },
+    /// Recompute the entry when one of its sources is updated by the same refresh instruction.
+    /// Only applies to the types computed from other entries without accounts (`MostRecentOf`,
+    /// `CappedFloored`, `CappedMostRecentOf`, `MedianOf` and the Scope TWAPs).
+    MetadataRederiveOnSourceRefresh(bool),
+}
+
+impl UpdateOracleMappingAndMetadataEntry {
+    /// Role allowed to apply the update, on top of the admin
+    pub fn required_role(&self) -> Role {
+        match self {
+            UpdateOracleMappingAndMetadataEntry::RemoveEntry
+            | UpdateOracleMappingAndMetadataEntry::MappingConfig { .. }
+            | UpdateOracleMappingAndMetadataEntry::MappingTwapEntry { .. }
+            | UpdateOracleMappingAndMetadataEntry::MappingTwapEnabledBitmask(_)
+            | UpdateOracleMappingAndMetadataEntry::MappingRefPrice { .. } => Role::MappingAdmin,
+            UpdateOracleMappingAndMetadataEntry::MetadataName(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceAgeSlots(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataGroupIdsBitset(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxSourceAge(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataRefPriceMaxAge { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataRederiveOnSourceRefresh(_) => {
+                Role::MetadataAdmin
+            }
+        }
+    }
+
+    /// Whether the update can change the entries the price of the entry depends on, or is
+    /// checked against, which requires checking the dependencies of the feed
+    pub fn changes_dependencies(&self) -> bool {
+        match self {
+            UpdateOracleMappingAndMetadataEntry::RemoveEntry
+            | UpdateOracleMappingAndMetadataEntry::MappingConfig { .. }
+            | UpdateOracleMappingAndMetadataEntry::MappingTwapEntry { .. }
+            | UpdateOracleMappingAndMetadataEntry::MappingRefPrice { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices { .. } => true,
+            UpdateOracleMappingAndMetadataEntry::MappingTwapEnabledBitmask(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataName(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceAgeSlots(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataGroupIdsBitset(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataMaxSourceAge(_)
+            | UpdateOracleMappingAndMetadataEntry::MetadataRefPriceMaxAge { .. }
+            | UpdateOracleMappingAndMetadataEntry::MetadataRederiveOnSourceRefresh(_) => false,
+        }
+    }
+
+    pub fn audit_kind(&self) -> AuditRecordKind {
+        match self {
+            UpdateOracleMappingAndMetadataEntry::RemoveEntry => AuditRecordKind::RemoveEntry,
+            UpdateOracleMappingAndMetadataEntry::MappingConfig { .. } => {
+                AuditRecordKind::MappingConfig
+            }
+            UpdateOracleMappingAndMetadataEntry::MappingTwapEntry { .. } => {
+                AuditRecordKind::MappingTwapEntry
+            }
+            UpdateOracleMappingAndMetadataEntry::MappingTwapEnabledBitmask(_) => {
+                AuditRecordKind::MappingTwapEnabledBitmask
+            }
+            UpdateOracleMappingAndMetadataEntry::MappingRefPrice { .. } => {
+                AuditRecordKind::MappingRefPrice
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataName(_) => AuditRecordKind::MetadataName,
+            UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceAgeSlots(_) => {
+                AuditRecordKind::MetadataMaxPriceAgeSlots
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataGroupIdsBitset(_) => {
+                AuditRecordKind::MetadataGroupIdsBitset
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove { .. } => {
+                AuditRecordKind::MetadataMaxPriceMove
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(_) => {
+                AuditRecordKind::MetadataConfidenceBps
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataMaxSourceAge(_) => {
+                AuditRecordKind::MetadataMaxSourceAge
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices { .. } => {
+                AuditRecordKind::MetadataAdditionalRefPrices
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataRefPriceMaxAge { .. } => {
+                AuditRecordKind::MetadataRefPriceMaxAge
+            }
+            UpdateOracleMappingAndMetadataEntry::MetadataRederiveOnSourceRefresh(_) => {
+                AuditRecordKind::MetadataRederiveOnSourceRefresh
+            }
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -53,7 +195,8 @@ impl UpdateOracleMappingAndMetadataEntriesWithId {
 }
 
 /// Handler expects as remaining accounts a `price_info` for each
-/// `UpdateOracleMappingAndMetadataEntry::MappingConfig`
+/// `UpdateOracleMappingAndMetadataEntry::MappingConfig`, followed by the audit log of the feed if
+/// it has one.
 /// Entry type when `price_info` is not used are expected to be `crate::ID`
 #[derive(Accounts)]
 #[instruction(
@@ -61,12 +204,12 @@ impl UpdateOracleMappingAndMetadataEntriesWithId {
     updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
 )]
 pub struct UpdateOracleMappingAndMetadata<'info> {
+    /// Admin, or holder of the roles required by the updates
     pub admin: Signer<'info>,
 
     #[account(
         seeds = [seeds::CONFIG, feed_name.as_bytes()],
         bump,
-        has_one = admin,
         has_one = oracle_mappings,
         has_one = oracle_prices,
         has_one = oracle_twaps,
@@ -92,23 +235,94 @@ pub struct UpdateOracleMappingAndMetadata<'info> {
 pub fn process(
     ctx: Context<UpdateOracleMappingAndMetadata>,
     updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
+) -> Result<()> {
+    let configuration = ctx.accounts.configuration.load()?;
+    check_update_roles(&configuration, ctx.accounts.admin.key, &updates)?;
+
+    let now = u64::try_from(Clock::get()?.unix_timestamp).unwrap();
+    if configuration.get_mapping_update_delay_s(now) != 0 {
+        msg!("Mapping updates are timelocked, use propose_mapping_update");
+        return err!(ScopeError::MappingUpdateTimelocked);
+    }
+    let (price_infos, audit_log) = split_audit_log(&configuration, ctx.remaining_accounts)?;
+
+    apply_updates(
+        &ctx.accounts.oracle_mappings,
+        &ctx.accounts.tokens_metadata,
+        &ctx.accounts.oracle_prices,
+        &ctx.accounts.oracle_twaps,
+        audit_log.as_ref(),
+        ctx.accounts.admin.key(),
+        price_infos,
+        updates,
+    )
+}
+
+/// Check that `signer` holds the roles required by all the `updates`, refusing an empty list or
+/// entry which would not require any role
+pub(crate) fn check_update_roles(
+    configuration: &Configuration,
+    signer: &Pubkey,
+    updates: &[UpdateOracleMappingAndMetadataEntriesWithId],
+) -> Result<()> {
+    if updates.is_empty() || updates.iter().any(|entry| entry.updates.is_empty()) {
+        return err!(ScopeError::EmptyUpdateList);
+    }
+    for entry in updates {
+        for update in entry.updates.iter() {
+            let role = update.required_role();
+            if !configuration.has_role(role, signer) {
+                msg!(
+                    "Update of entry {} requires the {:?} role",
+                    entry.entry_id,
+                    role
+                );
+                return err!(ScopeError::MissingRole);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply the updates, expecting a `price_info` in `price_infos` for each
+/// `UpdateOracleMappingAndMetadataEntry::MappingConfig`.
+///
+/// Each applied update is recorded in the `audit_log`, if any.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_updates(
+    oracle_mappings: &AccountLoader<OracleMappings>,
+    tokens_metadata: &AccountLoader<TokenMetadatas>,
+    oracle_prices: &AccountLoader<OraclePrices>,
+    oracle_twaps: &AccountLoader<OracleTwaps>,
+    audit_log: Option<&AccountLoader<AuditLog>>,
+    signer: Pubkey,
+    price_infos: &[AccountInfo],
+    updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
 ) -> Result<()> {
     // Sanity check, remaining accounts is at most the number of entry modified
     require_gte!(
         updates.len(),
-        ctx.remaining_accounts.len(),
+        price_infos.len(),
         ScopeError::UnexpectedAccount
     );
 
     // Load accounts
-    let mut oracle_mappings = ctx.accounts.oracle_mappings.load_mut()?;
-    let mut tokens_metadata = ctx.accounts.tokens_metadata.load_mut()?;
+    let mut oracle_mappings = oracle_mappings.load_mut()?;
+    let mut tokens_metadata = tokens_metadata.load_mut()?;
     let metadatas = &mut tokens_metadata.metadatas_array;
-    let mut oracle_prices = ctx.accounts.oracle_prices.load_mut()?;
-    let mut oracle_twaps = ctx.accounts.oracle_twaps.load_mut()?;
+    let mut oracle_prices = oracle_prices.load_mut()?;
+    let mut oracle_twaps = oracle_twaps.load_mut()?;
+    let mut audit_log = audit_log
+        .map(|audit_log| audit_log.load_mut())
+        .transpose()?;
     let clock = Clock::get()?;
 
-    let mut price_info_iter = ctx.remaining_accounts.iter();
+    let mut price_info_iter = price_infos.iter();
+
+    // Type and dependencies of the updated entries before the updates, only needed if the
+    // dependencies of the feed can change
+    let changes_dependencies = changes_dependencies(updates);
+    let mut initial_entries: BTreeMap<usize, EntryDependencies> = BTreeMap::new();
 
     for entry in updates {
         let UpdateOracleMappingAndMetadataEntriesWithId {
@@ -120,6 +334,11 @@ pub fn process(
 
         let current_type = oracle_mappings.get_entry_type(entry_id)?;
         let current_mapping_pk = oracle_mappings.get_entry_mapping_pk(entry_id);
+        if changes_dependencies {
+            initial_entries.entry(entry_id).or_insert_with(|| {
+                Feed::new(&oracle_mappings, &metadatas[..]).get_entry_dependencies(entry_id)
+            });
+        }
 
         msg!("**********************************");
         let old_name = if oracle_mappings.is_entry_used(entry_id) {
@@ -160,6 +379,11 @@ pub fn process(
         msg!("{:?}", metadatas[entry_id]);
 
         for update in entry_updates {
+            validate_update(entry_id, &update)?;
+            let audit_kind = update.audit_kind();
+            let old_value =
+                audit_value(audit_kind, &oracle_mappings, &metadatas[entry_id], entry_id);
+
             match update {
                 UpdateOracleMappingAndMetadataEntry::RemoveEntry => {
                     mapping_updated_check()?;
@@ -176,11 +400,6 @@ pub fn process(
                 } => {
                     mapping_updated_check()?;
 
-                    if new_price_type.is_twap() {
-                        msg!("Use MappingTwapEntry to set TWAP entries");
-                        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
-                    }
-
                     let price_info = price_info_iter
                         .next()
                         .ok_or(ScopeError::MissingPriceAccount)?;
@@ -297,7 +516,6 @@ pub fn process(
                     msg!("Updating ref price from \"{old_target_name:?}\" - {old_ref_price:?} - {old_ref_price_tolerance_bps:?}bps to \"{target_name:?}\" - {ref_price_index:?} - {ref_price_tolerance_bps:?}bps",);
 
                     if let Some(ref_price_index) = ref_price_index {
-                        require_gt!(MAX_ENTRIES_U16, ref_price_index, ScopeError::BadTokenNb);
                         if !oracle_mappings.is_entry_used(ref_price_index.into()) {
                             msg!("WARNING: Reference price entry {ref_price_index} is not defined",);
                         }
@@ -336,6 +554,102 @@ pub fn process(
                     );
                     metadatas[entry_id].group_ids_bitset = bitset;
                 }
+                UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove {
+                    max_move_bps,
+                    period_s,
+                    max_move_cap_bps,
+                } => {
+                    msg!(
+                        "Setting token max price move from {}bps per {}s (cap {}bps) to {}bps per {}s (cap {}bps)",
+                        metadatas[entry_id].max_price_move_bps,
+                        metadatas[entry_id].max_price_move_period_s,
+                        metadatas[entry_id].max_price_move_cap_bps,
+                        max_move_bps,
+                        period_s,
+                        max_move_cap_bps,
+                    );
+                    metadatas[entry_id].max_price_move_bps = max_move_bps;
+                    metadatas[entry_id].max_price_move_period_s = period_s;
+                    metadatas[entry_id].max_price_move_cap_bps = max_move_cap_bps;
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(confidence_bps) => {
+                    msg!(
+                        "Setting token confidence from {}bps to {}bps",
+                        metadatas[entry_id].confidence_bps,
+                        confidence_bps
+                    );
+                    metadatas[entry_id].confidence_bps = confidence_bps;
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataMaxSourceAge(max_age_s) => {
+                    msg!(
+                        "Setting token max source age from {}s to {}s",
+                        metadatas[entry_id].max_source_age_s,
+                        max_age_s
+                    );
+                    metadatas[entry_id].max_source_age_s = max_age_s;
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices {
+                    ref_price_indexes,
+                    quorum,
+                } => {
+                    msg!(
+                        "Setting token additional ref prices from {:?} (quorum {}) to {:?} (quorum {})",
+                        metadatas[entry_id].get_additional_ref_prices(),
+                        metadatas[entry_id].ref_price_quorum,
+                        ref_price_indexes,
+                        quorum
+                    );
+                    let mut additional_ref_prices = [0; MAX_ADDITIONAL_REF_PRICES];
+                    for (i, &ref_price_index) in ref_price_indexes.iter().enumerate() {
+                        if !oracle_mappings.is_entry_used(ref_price_index.into()) {
+                            msg!("WARNING: Reference price entry {ref_price_index} is not defined",);
+                        }
+                        additional_ref_prices[i] = ref_price_index;
+                    }
+                    metadatas[entry_id].additional_ref_prices = additional_ref_prices;
+                    metadatas[entry_id].additional_ref_prices_len =
+                        u8::try_from(ref_price_indexes.len()).unwrap();
+                    metadatas[entry_id].ref_price_quorum = quorum;
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataRefPriceMaxAge {
+                    max_age_s,
+                    stale_policy,
+                } => {
+                    msg!(
+                        "Setting token ref price max age from {}s ({:?}) to {}s ({:?})",
+                        metadatas[entry_id].ref_price_max_age_s,
+                        StaleRefPricePolicy::try_from(metadatas[entry_id].stale_ref_price_policy),
+                        max_age_s,
+                        stale_policy
+                    );
+                    metadatas[entry_id].ref_price_max_age_s = max_age_s;
+                    metadatas[entry_id].stale_ref_price_policy = stale_policy.into();
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataRederiveOnSourceRefresh(rederive) => {
+                    msg!(
+                        "Setting token rederive on source refresh from {} to {}",
+                        metadatas[entry_id].rederives_on_source_refresh(),
+                        rederive
+                    );
+                    let price_type = oracle_mappings.get_entry_type(entry_id)?;
+                    if rederive && !price_type.is_derived_without_accounts() {
+                        msg!("WARNING: entries of type {price_type:?} are not rederived on source refresh");
+                    }
+                    metadatas[entry_id].rederive_on_source_refresh = rederive.into();
+                }
+            }
+
+            if let Some(audit_log) = audit_log.as_mut() {
+                let new_value =
+                    audit_value(audit_kind, &oracle_mappings, &metadatas[entry_id], entry_id);
+                audit_log.push(
+                    clock.slot,
+                    signer,
+                    u16::try_from(entry_id).unwrap(),
+                    audit_kind,
+                    &old_value,
+                    &new_value,
+                );
             }
         }
 
@@ -344,9 +658,339 @@ pub fn process(
         msg!("{:?}", metadatas[entry_id]);
     }
 
+    if !changes_dependencies {
+        return Ok(());
+    }
+    check_feed_dependencies(
+        &Feed::new(&oracle_mappings, &metadatas[..]),
+        &initial_entries,
+    )
+}
+
+fn changes_dependencies(updates: &[UpdateOracleMappingAndMetadataEntriesWithId]) -> bool {
+    updates
+        .iter()
+        .flat_map(|entry| entry.updates.iter())
+        .any(UpdateOracleMappingAndMetadataEntry::changes_dependencies)
+}
+
+/// Check the dependencies of the feed after updating the entries of `initial_entries`
+fn check_feed_dependencies(
+    feed: &impl FeedEntries,
+    initial_entries: &BTreeMap<usize, EntryDependencies>,
+) -> Result<()> {
+    check_dependents(feed, initial_entries)?;
+
+    // The feed had no cycle before the updates, a new one goes through an updated entry
+    for &entry_id in initial_entries.keys() {
+        if let Err(e) = check_no_dependency_cycle(feed, entry_id) {
+            msg!("The price of entry {entry_id} depends on itself");
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the `updates` against the current state of the feed without applying them, expecting a
+/// `price_info` in `price_infos` for each `UpdateOracleMappingAndMetadataEntry::MappingConfig`.
+///
+/// The feed may change before the updates are applied, `apply_updates` checks them again.
+pub(crate) fn check_updates(
+    oracle_mappings: &OracleMappings,
+    metadatas: &[TokenMetadata],
+    price_infos: &[AccountInfo],
+    updates: &[UpdateOracleMappingAndMetadataEntriesWithId],
+    clock: &Clock,
+) -> Result<()> {
+    let feed = Feed::new(oracle_mappings, metadatas);
+    let mut price_info_iter = price_infos.iter();
+    let mut initial_entries: BTreeMap<usize, EntryDependencies> = BTreeMap::new();
+    let mut updated_entries: BTreeMap<usize, UpdatedEntry> = BTreeMap::new();
+
+    for entry in updates {
+        let entry_id: usize = entry.entry_id.into();
+        require_gt!(MAX_ENTRIES, entry_id, ScopeError::BadTokenNb);
+
+        let initial_entry = initial_entries
+            .entry(entry_id)
+            .or_insert_with(|| feed.get_entry_dependencies(entry_id));
+        let updated_entry = updated_entries
+            .entry(entry_id)
+            .or_insert_with(|| UpdatedEntry {
+                price_type: initial_entry.price_type,
+                price_sources: initial_entry.price_sources.clone(),
+                ref_price: oracle_mappings.get_ref_price(entry_id),
+                additional_ref_prices: metadatas[entry_id].get_additional_ref_prices().to_vec(),
+            });
+        // Unused entries are reset before being updated
+        if updated_entry.price_type.is_none() {
+            *updated_entry = UpdatedEntry::default();
+        }
+
+        let mut mapping_has_been_updated = false;
+        for update in entry.updates.iter() {
+            validate_update(entry_id, update)?;
+            match update {
+                UpdateOracleMappingAndMetadataEntry::RemoveEntry
+                | UpdateOracleMappingAndMetadataEntry::MappingConfig { .. }
+                | UpdateOracleMappingAndMetadataEntry::MappingTwapEntry { .. } => {
+                    if mapping_has_been_updated {
+                        msg!("Mapping config updated twice for entry {entry_id}");
+                        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+                    }
+                    mapping_has_been_updated = true;
+                }
+                _ => {}
+            }
+            match update {
+                UpdateOracleMappingAndMetadataEntry::RemoveEntry => {
+                    *updated_entry = UpdatedEntry::default();
+                }
+                UpdateOracleMappingAndMetadataEntry::MappingConfig {
+                    price_type,
+                    generic_data,
+                } => {
+                    let price_info = price_info_iter
+                        .next()
+                        .ok_or(ScopeError::MissingPriceAccount)?;
+                    validate_oracle_cfg(
+                        *price_type,
+                        maybe_account(price_info),
+                        generic_data,
+                        clock,
+                    )?;
+                    updated_entry.price_type = Some(*price_type);
+                    updated_entry.price_sources =
+                        get_composite_source_entries(*price_type, generic_data).unwrap_or_default();
+                }
+                UpdateOracleMappingAndMetadataEntry::MappingTwapEntry {
+                    price_type,
+                    twap_source,
+                } => {
+                    updated_entry.price_type = Some(*price_type);
+                    updated_entry.price_sources = vec![*twap_source];
+                }
+                UpdateOracleMappingAndMetadataEntry::MappingRefPrice {
+                    ref_price_index, ..
+                } => {
+                    updated_entry.ref_price = *ref_price_index;
+                }
+                UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices {
+                    ref_price_indexes,
+                    ..
+                } => {
+                    updated_entry.additional_ref_prices = ref_price_indexes.clone();
+                }
+                _ => {}
+            }
+        }
+        if let Some(ref_price) = updated_entry.ref_price {
+            if updated_entry.additional_ref_prices.contains(&ref_price) {
+                msg!("Ref price {ref_price} of entry {entry_id} is also an additional ref price");
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+        }
+    }
+    if !changes_dependencies(updates) {
+        return Ok(());
+    }
+    let updated_feed = UpdatedFeed {
+        feed,
+        updated_entries: updated_entries
+            .into_iter()
+            .map(|(entry_id, entry)| (entry_id, entry.into()))
+            .collect(),
+    };
+    check_feed_dependencies(&updated_feed, &initial_entries)
+}
+
+/// Mapping config and ref prices of an entry as set by a batch of updates
+#[derive(Default)]
+struct UpdatedEntry {
+    price_type: Option<OracleType>,
+    price_sources: Vec<u16>,
+    ref_price: Option<u16>,
+    additional_ref_prices: Vec<u16>,
+}
+
+impl From<UpdatedEntry> for EntryDependencies {
+    fn from(entry: UpdatedEntry) -> Self {
+        match entry.price_type {
+            None => EntryDependencies::default(),
+            price_type => EntryDependencies {
+                price_type,
+                price_sources: entry.price_sources,
+                ref_prices: entry
+                    .ref_price
+                    .into_iter()
+                    .chain(entry.additional_ref_prices)
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Checks of an update not depending on the state of the feed
+fn validate_update(entry_id: usize, update: &UpdateOracleMappingAndMetadataEntry) -> Result<()> {
+    match update {
+        UpdateOracleMappingAndMetadataEntry::MappingConfig { price_type, .. } => {
+            if price_type.is_twap() {
+                msg!("Use MappingTwapEntry to set TWAP entries");
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+        }
+        UpdateOracleMappingAndMetadataEntry::MappingTwapEntry {
+            price_type,
+            twap_source,
+        } => {
+            price_type.to_ema_type()?;
+            require_gt!(
+                MAX_ENTRIES_U16,
+                *twap_source,
+                ScopeError::TwapSourceIndexOutOfRange
+            );
+        }
+        UpdateOracleMappingAndMetadataEntry::MappingTwapEnabledBitmask(twap_enabled_bitmask) => {
+            TwapEnabledBitmask::try_from(*twap_enabled_bitmask)?;
+        }
+        UpdateOracleMappingAndMetadataEntry::MappingRefPrice {
+            ref_price_index, ..
+        } => {
+            if let Some(ref_price_index) = ref_price_index {
+                require_gt!(MAX_ENTRIES_U16, *ref_price_index, ScopeError::BadTokenNb);
+            }
+        }
+        UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove {
+            max_move_bps,
+            period_s,
+            max_move_cap_bps,
+        } => {
+            if *max_move_bps != 0 && *period_s == 0 {
+                msg!("Max price move period must be non-zero");
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+            if *max_move_bps != 0 && *max_move_cap_bps < (*max_move_bps).max(MIN_PRICE_MOVE_CAP_BPS)
+            {
+                msg!(
+                    "Max price move cap must be at least the max price move per period and {}bps",
+                    MIN_PRICE_MOVE_CAP_BPS
+                );
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+        }
+        UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(confidence_bps) => {
+            if *confidence_bps > FULL_BPS {
+                msg!("Confidence cannot exceed {}bps", FULL_BPS);
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+        }
+        UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices {
+            ref_price_indexes,
+            quorum,
+        } => {
+            if ref_price_indexes.len() > MAX_ADDITIONAL_REF_PRICES {
+                msg!("At most {MAX_ADDITIONAL_REF_PRICES} additional ref prices can be set");
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+            // The mapping ref price counts as one reference
+            if usize::from(*quorum) > ref_price_indexes.len() + 1 {
+                msg!("Quorum cannot exceed the number of ref prices");
+                return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+            }
+            for (i, &ref_price_index) in ref_price_indexes.iter().enumerate() {
+                require_gt!(MAX_ENTRIES_U16, ref_price_index, ScopeError::BadTokenNb);
+                if usize::from(ref_price_index) == entry_id
+                    || ref_price_indexes[..i].contains(&ref_price_index)
+                {
+                    msg!("Invalid ref price {ref_price_index}: self or duplicate");
+                    return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+                }
+            }
+        }
+        UpdateOracleMappingAndMetadataEntry::RemoveEntry
+        | UpdateOracleMappingAndMetadataEntry::MetadataName(_)
+        | UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceAgeSlots(_)
+        | UpdateOracleMappingAndMetadataEntry::MetadataGroupIdsBitset(_)
+        | UpdateOracleMappingAndMetadataEntry::MetadataMaxSourceAge(_)
+        | UpdateOracleMappingAndMetadataEntry::MetadataRefPriceMaxAge { .. }
+        | UpdateOracleMappingAndMetadataEntry::MetadataRederiveOnSourceRefresh(_) => {}
+    }
     Ok(())
 }
 
+/// Serialize the fields of the entry that a change of type `kind` touches
+fn audit_value(
+    kind: AuditRecordKind,
+    oracle_mappings: &OracleMappings,
+    metadata: &TokenMetadata,
+    entry_id: usize,
+) -> Vec<u8> {
+    let mut value = Vec::new();
+    match kind {
+        AuditRecordKind::RemoveEntry | AuditRecordKind::MappingConfig => {
+            value.extend_from_slice(oracle_mappings.price_info_accounts[entry_id].as_ref());
+            value.push(oracle_mappings.price_types[entry_id]);
+            value.extend_from_slice(&oracle_mappings.generic[entry_id]);
+            value.extend_from_slice(
+                &oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id].to_le_bytes(),
+            );
+        }
+        AuditRecordKind::MappingTwapEntry => {
+            value.push(oracle_mappings.price_types[entry_id]);
+            value.extend_from_slice(
+                &oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id].to_le_bytes(),
+            );
+        }
+        AuditRecordKind::MappingTwapEnabledBitmask => {
+            value.push(oracle_mappings.get_twap_enabled_bitmask(entry_id).into());
+        }
+        AuditRecordKind::MappingRefPrice => {
+            value.extend_from_slice(
+                &oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id].to_le_bytes(),
+            );
+            value.extend_from_slice(&oracle_mappings.ref_price[entry_id].to_le_bytes());
+        }
+        AuditRecordKind::MetadataName => value.extend_from_slice(&metadata.name),
+        AuditRecordKind::MetadataMaxPriceAgeSlots => {
+            value.extend_from_slice(&metadata.max_age_price_slots.to_le_bytes());
+        }
+        AuditRecordKind::MetadataGroupIdsBitset => {
+            value.extend_from_slice(&metadata.group_ids_bitset.to_le_bytes());
+        }
+        AuditRecordKind::MetadataMaxPriceMove => {
+            value.extend_from_slice(&metadata.max_price_move_bps.to_le_bytes());
+            value.extend_from_slice(&metadata.max_price_move_period_s.to_le_bytes());
+            value.extend_from_slice(&metadata.max_price_move_cap_bps.to_le_bytes());
+        }
+        AuditRecordKind::MetadataConfidenceBps => {
+            value.extend_from_slice(&metadata.confidence_bps.to_le_bytes());
+        }
+        AuditRecordKind::MetadataMaxSourceAge => {
+            value.extend_from_slice(&metadata.max_source_age_s.to_le_bytes());
+        }
+        AuditRecordKind::MetadataAdditionalRefPrices => {
+            for ref_price in metadata.additional_ref_prices {
+                value.extend_from_slice(&ref_price.to_le_bytes());
+            }
+            value.push(metadata.additional_ref_prices_len);
+            value.push(metadata.ref_price_quorum);
+        }
+        AuditRecordKind::MetadataRefPriceMaxAge => {
+            value.extend_from_slice(&metadata.ref_price_max_age_s.to_le_bytes());
+            value.push(metadata.stale_ref_price_policy);
+        }
+        AuditRecordKind::MetadataRederiveOnSourceRefresh => {
+            value.push(metadata.rederive_on_source_refresh);
+        }
+        AuditRecordKind::MoveEntry
+        | AuditRecordKind::CopyEntry
+        | AuditRecordKind::SetAdminCached
+        | AuditRecordKind::ApproveAdminCached => {}
+    }
+    value
+}
+
 fn maybe_get_entry_name<'a>(
     oracle_mappings: &'_ OracleMappings,
     metadatas: &'a [TokenMetadata],
@@ -358,3 +1002,369 @@ fn maybe_get_entry_name<'a>(
         "<unused>"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::error_code,
+        states::PendingMappingUpdate,
+        utils::test_utils::{
+            most_recent_of_data, new_feed, set_most_recent_of_entry, set_pyth_entry,
+        },
+    };
+
+    fn assert_scope_error(result: Result<()>, expected: ScopeError) {
+        let err = result.unwrap_err();
+        assert_eq!(error_code(&err), Some(expected.into()));
+    }
+
+    fn most_recent_of(sources: &[u16]) -> UpdateOracleMappingAndMetadataEntry {
+        UpdateOracleMappingAndMetadataEntry::MappingConfig {
+            price_type: OracleType::MostRecentOf,
+            generic_data: most_recent_of_data(sources),
+        }
+    }
+
+    /// Feed with Pyth entries 0 and 1 and entry 2 the most recent of entry 0
+    fn feed_with_dependent() -> (Box<OracleMappings>, Vec<TokenMetadata>) {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_pyth_entry(&mut oracle_mappings, 1);
+        set_most_recent_of_entry(&mut oracle_mappings, 2, &[0]);
+        (oracle_mappings, metadatas)
+    }
+
+    /// Check `updates` with the `crate::ID` placeholder as price account of every `MappingConfig`
+    fn check(
+        oracle_mappings: &OracleMappings,
+        metadatas: &[TokenMetadata],
+        updates: Vec<(u16, Vec<UpdateOracleMappingAndMetadataEntry>)>,
+    ) -> Result<()> {
+        let updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId> = updates
+            .into_iter()
+            .map(|(entry_id, updates)| {
+                UpdateOracleMappingAndMetadataEntriesWithId::new(entry_id, updates)
+            })
+            .collect();
+        let key = crate::ID;
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data: [u8; 0] = [];
+        let no_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        let price_infos =
+            vec![no_account; PendingMappingUpdate::price_info_accounts_count(&updates)];
+        check_updates(
+            oracle_mappings,
+            metadatas,
+            &price_infos,
+            &updates,
+            &Clock::default(),
+        )
+    }
+
+    #[test]
+    fn test_validate_update() {
+        assert!(validate_update(0, &most_recent_of(&[1])).is_ok());
+        assert_scope_error(
+            validate_update(
+                0,
+                &UpdateOracleMappingAndMetadataEntry::MappingConfig {
+                    price_type: OracleType::ScopeTwap1h,
+                    generic_data: [0; 20],
+                },
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+        assert_scope_error(
+            validate_update(
+                0,
+                &UpdateOracleMappingAndMetadataEntry::MappingTwapEntry {
+                    price_type: OracleType::ScopeTwap1h,
+                    twap_source: MAX_ENTRIES_U16,
+                },
+            ),
+            ScopeError::TwapSourceIndexOutOfRange,
+        );
+        assert_scope_error(
+            validate_update(
+                0,
+                &UpdateOracleMappingAndMetadataEntry::MetadataConfidenceBps(FULL_BPS + 1),
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+        assert_scope_error(
+            validate_update(
+                0,
+                &UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove {
+                    max_move_bps: 100,
+                    period_s: 60,
+                    max_move_cap_bps: 50,
+                },
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+        // A missing or tiny cap could block the entry on a usual price move
+        for max_move_cap_bps in [0, MIN_PRICE_MOVE_CAP_BPS - 1] {
+            assert_scope_error(
+                validate_update(
+                    0,
+                    &UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove {
+                        max_move_bps: 100,
+                        period_s: 60,
+                        max_move_cap_bps,
+                    },
+                ),
+                ScopeError::InvalidUpdateSequenceOrAccounts,
+            );
+        }
+        for (max_move_bps, max_move_cap_bps) in [(100, MIN_PRICE_MOVE_CAP_BPS), (0, 0)] {
+            validate_update(
+                0,
+                &UpdateOracleMappingAndMetadataEntry::MetadataMaxPriceMove {
+                    max_move_bps,
+                    period_s: 60,
+                    max_move_cap_bps,
+                },
+            )
+            .unwrap();
+        }
+        for ref_price_indexes in [vec![0], vec![1, 1]] {
+            assert_scope_error(
+                validate_update(
+                    0,
+                    &UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices {
+                        ref_price_indexes,
+                        quorum: 0,
+                    },
+                ),
+                ScopeError::InvalidUpdateSequenceOrAccounts,
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_updates_validates_the_oracle_config() {
+        let (oracle_mappings, metadatas) = feed_with_dependent();
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![(3, vec![most_recent_of(&[1])])]
+        )
+        .is_ok());
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(3, vec![most_recent_of(&[])])],
+            ),
+            ScopeError::MostRecentOfInvalidSourceIndices,
+        );
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(3, vec![most_recent_of(&[1]), most_recent_of(&[0])])],
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+    }
+
+    #[test]
+    fn test_check_updates_refuses_cycles() {
+        let (oracle_mappings, metadatas) = feed_with_dependent();
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![
+                    (0, vec![most_recent_of(&[2])]),
+                    (2, vec![most_recent_of(&[0, 1])]),
+                ],
+            ),
+            ScopeError::DependencyCycle,
+        );
+        // The cycle goes through the entries updated in the batch
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![
+                    (3, vec![most_recent_of(&[1])]),
+                    (1, vec![most_recent_of(&[3])]),
+                ],
+            ),
+            ScopeError::DependencyCycle,
+        );
+    }
+
+    #[test]
+    fn test_check_updates_only_checks_the_dependencies_if_they_can_change() {
+        // Entry 3 depends on itself, which the dependency checks refuse
+        let (mut oracle_mappings, metadatas) = feed_with_dependent();
+        set_most_recent_of_entry(&mut oracle_mappings, 3, &[3]);
+        let rename = UpdateOracleMappingAndMetadataEntry::MetadataName("B".to_string());
+        let ref_price = UpdateOracleMappingAndMetadataEntry::MappingRefPrice {
+            ref_price_index: Some(0),
+            ref_price_tolerance_bps: Some(100),
+        };
+        assert!(!rename.changes_dependencies());
+        assert!(ref_price.changes_dependencies());
+
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![(3, vec![rename.clone()])]
+        )
+        .is_ok());
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(3, vec![rename, ref_price])],
+            ),
+            ScopeError::DependencyCycle,
+        );
+    }
+
+    #[test]
+    fn test_check_updates_requires_reviewing_the_dependents() {
+        let (oracle_mappings, metadatas) = feed_with_dependent();
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(0, vec![UpdateOracleMappingAndMetadataEntry::RemoveEntry])],
+            ),
+            ScopeError::EntryHasDependents,
+        );
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(0, vec![most_recent_of(&[1])])],
+            ),
+            ScopeError::EntryHasDependents,
+        );
+        // Only renaming the dependent is not enough
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![
+                    (0, vec![most_recent_of(&[1])]),
+                    (
+                        2,
+                        vec![UpdateOracleMappingAndMetadataEntry::MetadataName(
+                            "B".to_string(),
+                        )],
+                    ),
+                ],
+            ),
+            ScopeError::EntryHasDependents,
+        );
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![
+                (0, vec![most_recent_of(&[1])]),
+                (2, vec![most_recent_of(&[0, 1])]),
+            ],
+        )
+        .is_ok());
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![
+                (2, vec![UpdateOracleMappingAndMetadataEntry::RemoveEntry]),
+                (0, vec![UpdateOracleMappingAndMetadataEntry::RemoveEntry]),
+            ],
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_check_updates_refuses_duplicate_ref_prices() {
+        let (mut oracle_mappings, metadatas) = feed_with_dependent();
+        let ref_price = |ref_price_index| UpdateOracleMappingAndMetadataEntry::MappingRefPrice {
+            ref_price_index,
+            ref_price_tolerance_bps: Some(100),
+        };
+        let additional_ref_prices =
+            |ref_price_indexes| UpdateOracleMappingAndMetadataEntry::MetadataAdditionalRefPrices {
+                ref_price_indexes,
+                quorum: 0,
+            };
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(2, vec![additional_ref_prices(vec![1]), ref_price(Some(1))])],
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![(2, vec![ref_price(Some(0)), additional_ref_prices(vec![1])])],
+        )
+        .is_ok());
+
+        // Against the ref price already set in the mapping
+        oracle_mappings.set_ref_price(2, Some(1));
+        assert_scope_error(
+            check(
+                &oracle_mappings,
+                &metadatas,
+                vec![(2, vec![additional_ref_prices(vec![0, 1])])],
+            ),
+            ScopeError::InvalidUpdateSequenceOrAccounts,
+        );
+        assert!(check(
+            &oracle_mappings,
+            &metadatas,
+            vec![(2, vec![ref_price(None), additional_ref_prices(vec![0, 1])])],
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_update_roles() {
+        let mut configuration: Box<Configuration> = Box::new(bytemuck::Zeroable::zeroed());
+        configuration.admin = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let updates = |updates| vec![UpdateOracleMappingAndMetadataEntriesWithId::new(0, updates)];
+        let rename = UpdateOracleMappingAndMetadataEntry::MetadataName("name".to_string());
+
+        assert!(check_update_roles(
+            &configuration,
+            &configuration.admin,
+            &updates(vec![rename.clone()])
+        )
+        .is_ok());
+        assert_scope_error(
+            check_update_roles(&configuration, &signer, &updates(vec![rename])),
+            ScopeError::MissingRole,
+        );
+        // Nothing to check the roles of, even for the admin
+        for updates in [Vec::new(), updates(Vec::new())] {
+            assert_scope_error(
+                check_update_roles(&configuration, &configuration.admin, &updates),
+                ScopeError::EmptyUpdateList,
+            );
+            assert_scope_error(
+                check_update_roles(&configuration, &signer, &updates),
+                ScopeError::EmptyUpdateList,
+            );
+        }
+    }
+}
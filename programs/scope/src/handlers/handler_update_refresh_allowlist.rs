@@ -0,0 +1,77 @@
+use anchor_lang::{prelude::*, Accounts};
+
+use crate::{
+    oracles::check_context,
+    states::{
+        audit_log::record_change, AuditLog, AuditRecordKind, Configuration, RefreshAllowlist,
+        AUDIT_NO_ENTRY,
+    },
+    ScopeError,
+};
+
+#[derive(Accounts)]
+#[instruction(feed_name: String)]
+pub struct UpdateRefreshAllowlist<'info> {
+    admin: Signer<'info>,
+
+    #[account(mut, seeds = [b"conf", feed_name.as_bytes()], bump, has_one = admin)]
+    pub configuration: AccountLoader<'info, Configuration>,
+
+    /// Required if the feed has an audit log
+    #[account(mut)]
+    pub audit_log: Option<AccountLoader<'info, AuditLog>>,
+}
+
+pub fn process(
+    ctx: Context<UpdateRefreshAllowlist>,
+    feed_name: String,
+    allowlist: RefreshAllowlist,
+    program_id: Pubkey,
+    allowed: bool,
+) -> Result<()> {
+    check_context(&ctx)?;
+
+    msg!(
+        "feed_name {} setting {:?} refresh allowlist entry {} to {}",
+        feed_name,
+        allowlist,
+        program_id,
+        allowed
+    );
+
+    if program_id == Pubkey::default() || program_id == crate::ID {
+        return err!(ScopeError::InvalidUpdateSequenceOrAccounts);
+    }
+
+    let configuration = &mut ctx.accounts.configuration.load_mut()?;
+    let entries = configuration.get_refresh_allowlist_mut(allowlist);
+    let existing = entries.iter().position(|entry| *entry == program_id);
+
+    match (existing, allowed) {
+        (Some(_), true) | (None, false) => {
+            msg!("Allowlist already up to date");
+        }
+        (Some(pos), false) => {
+            entries[pos] = Pubkey::default();
+        }
+        (None, true) => {
+            let free_slot = entries
+                .iter_mut()
+                .find(|entry| **entry == Pubkey::default())
+                .ok_or(ScopeError::RefreshAllowlistFull)?;
+            *free_slot = program_id;
+        }
+    }
+
+    record_change(
+        configuration,
+        &ctx.accounts.audit_log,
+        ctx.accounts.admin.key(),
+        AUDIT_NO_ENTRY,
+        AuditRecordKind::UpdateRefreshAllowlist,
+        &(allowlist, program_id, existing.is_some()).try_to_vec()?,
+        &(allowlist, program_id, allowed).try_to_vec()?,
+    )?;
+
+    Ok(())
+}
@@ -1,23 +1,49 @@
 pub mod handler_approve_admin_cached;
+pub mod handler_cancel_mapping_update;
+pub mod handler_close_feed;
 pub mod handler_close_mint_map;
 pub mod handler_create_mint_map;
+pub mod handler_execute_mapping_update;
 pub mod handler_initialize;
+pub mod handler_initialize_audit_log;
+pub mod handler_move_entry;
+pub mod handler_propose_mapping_update;
 pub mod handler_refresh_chainlink_price;
+pub mod handler_refresh_price_group;
 pub mod handler_refresh_prices;
 pub mod handler_refresh_pyth_lazer_price;
 pub mod handler_reset_twap;
 pub mod handler_resume_chainlinkx_price;
 pub mod handler_set_admin_cached;
+pub mod handler_set_extension_page;
+pub mod handler_set_mapping_update_delay;
+pub mod handler_set_paused;
+pub mod handler_set_role;
+pub mod handler_simulate_mapping_entry;
 pub mod handler_update_mapping_and_metadata;
+pub mod handler_update_refresh_allowlist;
 
 pub use handler_approve_admin_cached::*;
+pub use handler_cancel_mapping_update::*;
+pub use handler_close_feed::*;
 pub use handler_close_mint_map::*;
 pub use handler_create_mint_map::*;
+pub use handler_execute_mapping_update::*;
 pub use handler_initialize::*;
+pub use handler_initialize_audit_log::*;
+pub use handler_move_entry::*;
+pub use handler_propose_mapping_update::*;
 pub use handler_refresh_chainlink_price::*;
+pub use handler_refresh_price_group::*;
 pub use handler_refresh_prices::*;
 pub use handler_refresh_pyth_lazer_price::*;
 pub use handler_reset_twap::*;
 pub use handler_resume_chainlinkx_price::*;
 pub use handler_set_admin_cached::*;
+pub use handler_set_extension_page::*;
+pub use handler_set_mapping_update_delay::*;
+pub use handler_set_paused::*;
+pub use handler_set_role::*;
+pub use handler_simulate_mapping_entry::*;
 pub use handler_update_mapping_and_metadata::*;
+pub use handler_update_refresh_allowlist::*;
@@ -1,5 +1,6 @@
 #![allow(clippy::result_large_err)] //Needed because we can't change Anchor result type
 pub mod errors;
+pub mod events;
 pub mod oracles;
 pub mod program_id;
 pub mod states;
@@ -21,10 +22,13 @@ pub use yvaults;
 
 pub use crate::{
     errors::*,
-    handlers::handler_update_mapping_and_metadata::{
-        UpdateOracleMappingAndMetadataEntriesWithId, UpdateOracleMappingAndMetadataEntry,
+    handlers::{
+        handler_refresh_prices::RefreshStatus,
+        handler_update_mapping_and_metadata::{
+            UpdateOracleMappingAndMetadataEntriesWithId, UpdateOracleMappingAndMetadataEntry,
+        },
     },
-    states::{DatedPrice, Price},
+    states::{DatedPrice, PauseTarget, Price, RefreshAllowlist, Role},
     utils::scope_chain,
 };
 
@@ -45,6 +49,22 @@ pub mod scope {
         handler_initialize::process(ctx, feed_name)
     }
 
+    /// Attach an audit log to the feed, recording every mapping, metadata and admin change
+    /// from then on. The audit log account is pre-reserved outside the program.
+    pub fn initialize_audit_log(
+        ctx: Context<InitializeAuditLog>,
+        _feed_name: String,
+    ) -> Result<()> {
+        handler_initialize_audit_log::process(ctx)
+    }
+
+    /// Tokens are refreshed in the given order, derived entries must come after their sources
+    /// (see [`utils::dependencies::get_refresh_order`]).
+    ///
+    /// The pauses, the refresh allowlists of the configuration and the checks configured in the
+    /// token metadatas are applied to all the tokens.
+    ///
+    /// Returns one [`RefreshStatus`] byte per requested token through the return data
     pub fn refresh_price_list<'info>(
         ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
         tokens: Vec<u16>,
@@ -52,6 +72,24 @@ pub mod scope {
         handler_refresh_prices::refresh_price_list(ctx, &tokens)
     }
 
+    /// Deprecated alias of `refresh_price_list`, which now takes the same accounts
+    pub fn refresh_price_list_v2<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
+        tokens: Vec<u16>,
+    ) -> Result<()> {
+        handler_refresh_prices::refresh_price_list(ctx, &tokens)
+    }
+
+    /// Refresh all the entries tagged with `group_id` in their token metadata
+    ///
+    /// Returns one [`RefreshStatus`] byte per refreshed entry through the return data
+    pub fn refresh_price_group<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshList<'info>>,
+        group_id: u8,
+    ) -> Result<()> {
+        handler_refresh_price_group::refresh_price_group(ctx, group_id)
+    }
+
     pub fn refresh_chainlink_price<'info>(
         ctx: Context<'_, '_, '_, 'info, RefreshChainlinkPrice<'info>>,
         token: u16,
@@ -66,6 +104,8 @@ pub mod scope {
 
     /// IMPORTANT: we assume the tokens passed in to this ix are in the same order in which
     /// they are found in the message payload. Thus, we rely on the client to do this work
+    ///
+    /// Returns one [`RefreshStatus`] byte per requested token through the return data
     pub fn refresh_pyth_lazer_price<'info>(
         ctx: Context<'_, '_, '_, 'info, RefreshPythLazerPrice<'info>>,
         tokens: Vec<u16>,
@@ -89,6 +129,83 @@ pub mod scope {
         handler_update_mapping_and_metadata::process(ctx, updates)
     }
 
+    /// Propose mapping and metadata updates, executable once the feed mapping update delay
+    /// has elapsed.
+    ///
+    /// Expects the same remaining accounts as `update_mapping_and_metadata`
+    pub fn propose_mapping_update(
+        ctx: Context<ProposeMappingUpdate>,
+        _feed_name: String,
+        proposal_id: u64,
+        updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
+    ) -> Result<()> {
+        handler_propose_mapping_update::process(ctx, proposal_id, updates)
+    }
+
+    /// Apply a proposed mapping update whose delay has elapsed
+    pub fn execute_mapping_update(
+        ctx: Context<ExecuteMappingUpdate>,
+        _feed_name: String,
+        proposal_id: u64,
+    ) -> Result<()> {
+        handler_execute_mapping_update::process(ctx, proposal_id)
+    }
+
+    pub fn cancel_mapping_update(
+        ctx: Context<CancelMappingUpdate>,
+        _feed_name: String,
+        proposal_id: u64,
+    ) -> Result<()> {
+        handler_cancel_mapping_update::process(ctx, proposal_id)
+    }
+
+    /// Set the delay between the proposal and the execution of mapping updates.
+    /// A shorter delay only takes effect once the current delay has elapsed.
+    pub fn set_mapping_update_delay(
+        ctx: Context<SetMappingUpdateDelay>,
+        _feed_name: String,
+        delay_s: u64,
+    ) -> Result<()> {
+        handler_set_mapping_update_delay::process(ctx, delay_s)
+    }
+
+    /// Move the mapping, metadata, price and TWAP of `source` to the unused `destination`,
+    /// rewriting the references of the other entries to `source`, and reset `source`.
+    ///
+    /// Scope chains and consumers referencing `source` are not updated.
+    pub fn move_entry(
+        ctx: Context<MoveEntry>,
+        _feed_name: String,
+        source: u16,
+        destination: u16,
+    ) -> Result<()> {
+        handler_move_entry::process(ctx, source, destination, false)
+    }
+
+    /// Copy the mapping, metadata, price and TWAP of `source` to the unused `destination`
+    pub fn copy_entry(
+        ctx: Context<MoveEntry>,
+        _feed_name: String,
+        source: u16,
+        destination: u16,
+    ) -> Result<()> {
+        handler_move_entry::process(ctx, source, destination, true)
+    }
+
+    /// Validate a proposed mapping of `entry_id` and compute its price against the live
+    /// accounts, without writing anything.
+    ///
+    /// Returns the resulting `DatedPrice` (as its raw bytes) through the return data
+    pub fn simulate_mapping_entry<'info>(
+        ctx: Context<'_, '_, '_, 'info, SimulateMappingEntry<'info>>,
+        _feed_name: String,
+        entry_id: u16,
+        price_type: oracles::OracleType,
+        generic_data: [u8; 20],
+    ) -> Result<()> {
+        handler_simulate_mapping_entry::process(ctx, entry_id, price_type, generic_data)
+    }
+
     pub fn reset_twap(ctx: Context<ResetTwap>, token: u64, feed_name: String) -> Result<()> {
         let entry_id: usize = token
             .try_into()
@@ -108,6 +225,42 @@ pub mod scope {
         handler_approve_admin_cached::process(ctx, feed_name)
     }
 
+    /// Pause (`paused = true`) or resume the refreshes of the feed, a group or an entry.
+    /// Refresh instructions refuse paused entries when refreshing a single one, and skip them
+    /// otherwise.
+    pub fn set_paused(
+        ctx: Context<SetPaused>,
+        _feed_name: String,
+        target: PauseTarget,
+        paused: bool,
+    ) -> Result<()> {
+        handler_set_paused::process(ctx, target, paused)
+    }
+
+    /// Grant `role` to `holder`, replacing the previous holder.
+    /// `Pubkey::default()` revokes the role, leaving it to the admin only.
+    pub fn set_role(
+        ctx: Context<SetRole>,
+        _feed_name: String,
+        role: Role,
+        holder: Pubkey,
+    ) -> Result<()> {
+        handler_set_role::process(ctx, role, holder)
+    }
+
+    /// Add (`allowed = true`) or remove a program from one of the refresh allowlists.
+    /// Allowlisted programs may refresh prices through CPI or have instructions preceding
+    /// the refresh instructions in the transaction.
+    pub fn update_refresh_allowlist(
+        ctx: Context<UpdateRefreshAllowlist>,
+        feed_name: String,
+        allowlist: RefreshAllowlist,
+        program_id: Pubkey,
+        allowed: bool,
+    ) -> Result<()> {
+        handler_update_refresh_allowlist::process(ctx, feed_name, allowlist, program_id, allowed)
+    }
+
     pub fn create_mint_map(
         ctx: Context<CreateMintMap>,
         seed_pk: Pubkey,
@@ -118,6 +271,28 @@ pub mod scope {
         handler_create_mint_map::process(ctx, seed_pk, seed_id, bump, scope_chains)
     }
 
+    /// Link (`linked = true`) or unlink the feed `extension_feed_name` as the page `page` of
+    /// `feed_name`, the page holding the entries `MAX_ENTRIES * page..MAX_ENTRIES * (page + 1)`.
+    ///
+    /// Both feeds must have the same admin. Instructions address the entries of a page with
+    /// their index in the page, and references between entries (ref prices, TWAP sources,
+    /// scope chains) stay within a page.
+    pub fn set_extension_page(
+        ctx: Context<SetExtensionPage>,
+        _feed_name: String,
+        _extension_feed_name: String,
+        page: u8,
+        linked: bool,
+    ) -> Result<()> {
+        handler_set_extension_page::process(ctx, page, linked)
+    }
+
+    /// Close the configuration and the four accounts of a paused feed, sending their rent to
+    /// `recipient`. Pending mapping updates must be cancelled beforehand.
+    pub fn close_feed(ctx: Context<CloseFeed>, feed_name: String) -> Result<()> {
+        handler_close_feed::process(ctx, feed_name)
+    }
+
     pub fn close_mint_map(ctx: Context<CloseMintMap>) -> Result<()> {
         handler_close_mint_map::process(ctx)
     }
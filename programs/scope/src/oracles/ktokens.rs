@@ -24,7 +24,7 @@ use yvaults::{
 };
 
 use crate::{
-    utils::{account_deserialize, zero_copy_deserialize},
+    utils::{account_deserialize, check_account_owner, zero_copy_deserialize},
     warn, DatedPrice, Price, ScopeError, ScopeResult,
 };
 
@@ -51,6 +51,7 @@ where
     'a: 'b,
 {
     // Get the root account
+    check_account_owner(k_account, &[kamino::ID])?;
     let strategy_account_ref = zero_copy_deserialize::<WhirlpoolStrategy>(k_account)?;
 
     // extract the accounts from extra iterator
@@ -112,6 +113,14 @@ where
         strategy_account_ref.scope_prices,
         "scope_prices",
     )?;
+    check_extra_accounts_owners(
+        &strategy_account_ref,
+        global_config_account_info,
+        collateral_infos_account_info,
+        pool_account_info,
+        position_account_info,
+        scope_prices_account_info,
+    )?;
 
     // Deserialize accounts
     let collateral_infos_ref =
@@ -161,6 +170,43 @@ where
     })
 }
 
+pub fn validate_strategy_account(k_account: Option<&AccountInfo>) -> ScopeResult<()> {
+    let Some(k_account) = k_account else {
+        warn!("No kToken strategy account provided");
+        return Err(ScopeError::ExpectedPriceAccount);
+    };
+    check_account_owner(k_account, &[kamino::ID])?;
+    let _ = zero_copy_deserialize::<WhirlpoolStrategy>(k_account)?;
+    Ok(())
+}
+
+/// Check the owners of the accounts referenced by the strategy
+pub(super) fn check_extra_accounts_owners(
+    strategy: &WhirlpoolStrategy,
+    global_config: &AccountInfo,
+    collateral_infos: &AccountInfo,
+    pool: &AccountInfo,
+    position: &AccountInfo,
+    scope_prices: &AccountInfo,
+) -> ScopeResult<()> {
+    check_account_owner(global_config, &[kamino::ID])?;
+    check_account_owner(collateral_infos, &[kamino::ID])?;
+    check_account_owner(scope_prices, &[crate::ID])?;
+    let dex = DEX::try_from(strategy.strategy_dex).map_err(|_| {
+        msg!("Unknown strategy DEX {}", strategy.strategy_dex);
+        ScopeError::ConversionFailure
+    })?;
+    let dex_program_id = match dex {
+        DEX::Orca => whirlpool::ID,
+        DEX::Raydium => raydium_amm_v3::ID,
+    };
+    check_account_owner(pool, &[dex_program_id])?;
+    if strategy.position != Pubkey::default() {
+        check_account_owner(position, &[dex_program_id])?;
+    }
+    Ok(())
+}
+
 pub(super) fn get_clmm<'a, 'info>(
     pool: &'a AccountInfo<'info>,
     position: &'a AccountInfo<'info>,
@@ -9,9 +9,10 @@ use yvaults::{
     utils::{enums::LiquidityCalculationMode, price::TokenPrices},
 };
 
-use super::ktokens::price_utils;
+use super::ktokens::{check_extra_accounts_owners, price_utils};
 use crate::{
     utils::{
+        check_account_owner,
         math::{price_of_lamports_to_price_of_tokens, u64_div_to_price},
         zero_copy_deserialize,
     },
@@ -41,6 +42,7 @@ where
     'a: 'b,
 {
     // Get the root account
+    check_account_owner(k_account, &[kamino::ID])?;
     let strategy_account_ref = zero_copy_deserialize::<WhirlpoolStrategy>(k_account)?;
 
     // extract the accounts from extra iterator
@@ -102,6 +104,14 @@ where
         strategy_account_ref.scope_prices,
         "scope_prices",
     )?;
+    check_extra_accounts_owners(
+        &strategy_account_ref,
+        global_config_account_info,
+        collateral_infos_account_info,
+        pool_account_info,
+        position_account_info,
+        scope_prices_account_info,
+    )?;
 
     // Deserialize accounts
     let collateral_infos_ref =
@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+use decimal_wad::decimal::Decimal;
+
+use crate::{
+    states::OraclePrices, warn, DatedPrice, Price, ScopeError, ScopeResult, MAX_ENTRIES_U16,
+};
+
+pub const MEDIAN_OF_MAX_SOURCES: usize = 5;
+/// Value of the unused slots of [`MedianOfData::source_entries`]
+pub const MEDIAN_OF_UNUSED_SOURCE: u16 = u16::MAX;
+
+/// Unused source slots must be set to [`MEDIAN_OF_UNUSED_SOURCE`], any other out of range index
+/// is rejected when the mapping is validated.
+#[derive(Debug, AnchorDeserialize, AnchorSerialize)]
+pub struct MedianOfData {
+    pub source_entries: [u16; MEDIAN_OF_MAX_SOURCES],
+    pub sources_max_age_s: u64,
+    /// Minimum number of sources younger than `sources_max_age_s` to compute the median
+    pub min_fresh_sources: u8,
+}
+
+impl Default for MedianOfData {
+    fn default() -> Self {
+        Self {
+            source_entries: [MEDIAN_OF_UNUSED_SOURCE; MEDIAN_OF_MAX_SOURCES],
+            sources_max_age_s: 0,
+            min_fresh_sources: 0,
+        }
+    }
+}
+
+impl MedianOfData {
+    pub fn from_generic_data(mut buff: &[u8]) -> ScopeResult<Self> {
+        AnchorDeserialize::deserialize(&mut buff).map_err(|_| {
+            msg!("Failed to deserialize MedianOfData");
+            ScopeError::InvalidGenericData
+        })
+    }
+
+    pub fn to_generic_data(&self) -> [u8; 20] {
+        let mut buff = [0u8; 20];
+        let mut writer = &mut buff[..];
+        self.serialize(&mut writer)
+            .expect("Failed to serialize MedianOfData");
+        buff
+    }
+
+    fn used_source_entries(&self) -> impl Iterator<Item = u16> + '_ {
+        self.source_entries
+            .iter()
+            .copied()
+            .filter(|&entry| entry < MAX_ENTRIES_U16)
+    }
+}
+
+/// Median of the fresh source prices, with the timestamp and slot of the freshest of them.
+///
+/// Sources older than `sources_max_age_s` are ignored; with an even number of fresh sources
+/// the price is the mean of the two middle ones.
+pub fn get_price(
+    oracle_prices: &OraclePrices,
+    generic_data: &[u8],
+    clock: &Clock,
+) -> ScopeResult<DatedPrice> {
+    let data = MedianOfData::from_generic_data(generic_data)?;
+    let now: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .expect("Clock is in the past");
+
+    let mut fresh_prices: Vec<&DatedPrice> = data
+        .used_source_entries()
+        .filter_map(|entry| oracle_prices.prices.get(usize::from(entry)))
+        .filter(|dated_price| {
+            now.saturating_sub(dated_price.unix_timestamp) <= data.sources_max_age_s
+        })
+        .collect();
+
+    if fresh_prices.len() < usize::from(data.min_fresh_sources) || fresh_prices.is_empty() {
+        warn!(
+            "MedianOf: only {} fresh sources, {} required",
+            fresh_prices.len(),
+            data.min_fresh_sources
+        );
+        return Err(ScopeError::MedianOfQuorumNotMet);
+    }
+
+    fresh_prices.sort_unstable_by(|a, b| a.price.cmp(&b.price));
+    let middle = fresh_prices.len() / 2;
+    let price = if fresh_prices.len() % 2 == 1 {
+        fresh_prices[middle].price
+    } else {
+        let lower = Decimal::from(fresh_prices[middle - 1].price);
+        let upper = Decimal::from(fresh_prices[middle].price);
+        Price::from((lower + upper) / 2_u64)
+    };
+
+    let freshest = fresh_prices
+        .iter()
+        .max_by_key(|dated_price| dated_price.unix_timestamp)
+        .unwrap();
+
+    Ok(DatedPrice {
+        price,
+        last_updated_slot: freshest.last_updated_slot,
+        unix_timestamp: freshest.unix_timestamp,
+        generic_data: [0; 24],
+    })
+}
+
+pub fn validate_mapping_cfg(mapping: Option<&AccountInfo>, generic_data: &[u8]) -> ScopeResult<()> {
+    if mapping.is_some() {
+        warn!("No mapping account is expected for MedianOf oracle");
+        return Err(ScopeError::PriceAccountNotExpected);
+    }
+
+    let data = MedianOfData::from_generic_data(generic_data)?;
+
+    msg!(
+        "Validate MedianOf price with source_entries = {:?}, sources_max_age_s = {}, min_fresh_sources = {}",
+        data.source_entries,
+        data.sources_max_age_s,
+        data.min_fresh_sources
+    );
+
+    if let Some(invalid) = data
+        .source_entries
+        .iter()
+        .find(|&&entry| entry >= MAX_ENTRIES_U16 && entry != MEDIAN_OF_UNUSED_SOURCE)
+    {
+        warn!("Invalid source index {invalid} for MedianOf oracle, unused slots must be set to {MEDIAN_OF_UNUSED_SOURCE}");
+        return Err(ScopeError::CompositeOracleInvalidSourceIndex);
+    }
+
+    let sources: Vec<u16> = data.used_source_entries().collect();
+    if sources.is_empty() {
+        warn!("MedianOf oracle requires at least one source");
+        return Err(ScopeError::CompositeOracleInvalidSourceIndex);
+    }
+    for (i, source) in sources.iter().enumerate() {
+        if sources[..i].contains(source) {
+            warn!("Duplicate source index {source} for MedianOf oracle");
+            return Err(ScopeError::CompositeOracleInvalidSourceIndex);
+        }
+    }
+
+    if data.sources_max_age_s == 0 {
+        return Err(ScopeError::MedianOfInvalidMaxAge);
+    }
+
+    if data.min_fresh_sources == 0 || usize::from(data.min_fresh_sources) > sources.len() {
+        warn!(
+            "MedianOf quorum must be between 1 and the number of sources ({})",
+            sources.len()
+        );
+        return Err(ScopeError::MedianOfInvalidQuorum);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000_000;
+
+    fn oracle_prices(prices: &[(usize, u64, i64)]) -> Box<OraclePrices> {
+        let mut oracle_prices: Box<OraclePrices> = Box::new(bytemuck::Zeroable::zeroed());
+        for &(entry, value, timestamp) in prices {
+            oracle_prices.prices[entry] = DatedPrice {
+                price: Price { value, exp: 0 },
+                last_updated_slot: timestamp as u64,
+                unix_timestamp: timestamp as u64,
+                generic_data: [0; 24],
+            };
+        }
+        oracle_prices
+    }
+
+    fn generic_data(sources: &[u16], min_fresh_sources: u8) -> [u8; 20] {
+        let mut data = MedianOfData {
+            sources_max_age_s: 60,
+            min_fresh_sources,
+            ..Default::default()
+        };
+        data.source_entries[..sources.len()].copy_from_slice(sources);
+        data.to_generic_data()
+    }
+
+    fn clock() -> Clock {
+        Clock {
+            unix_timestamp: NOW,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_median_odd_count() {
+        let prices = oracle_prices(&[(1, 30, NOW), (2, 10, NOW - 5), (3, 20, NOW - 10)]);
+        let res = get_price(&prices, &generic_data(&[1, 2, 3], 3), &clock()).unwrap();
+        assert_eq!(res.price, Price { value: 20, exp: 0 });
+        assert_eq!(res.unix_timestamp, NOW as u64);
+    }
+
+    #[test]
+    fn test_median_even_count() {
+        let prices = oracle_prices(&[(1, 10, NOW), (2, 40, NOW), (3, 20, NOW), (4, 30, NOW)]);
+        let res = get_price(&prices, &generic_data(&[1, 2, 3, 4], 4), &clock()).unwrap();
+        assert_eq!(res.price, Price { value: 25, exp: 0 });
+    }
+
+    #[test]
+    fn test_median_ignores_stale_sources() {
+        let prices = oracle_prices(&[(1, 10, NOW - 61), (2, 40, NOW - 30), (3, 20, NOW)]);
+        let res = get_price(&prices, &generic_data(&[1, 2, 3], 2), &clock()).unwrap();
+        assert_eq!(res.price, Price { value: 30, exp: 0 });
+        assert_eq!(res.unix_timestamp, NOW as u64);
+    }
+
+    #[test]
+    fn test_median_quorum_not_met() {
+        let prices = oracle_prices(&[(1, 10, NOW - 61), (2, 40, NOW - 100), (3, 20, NOW)]);
+        assert_eq!(
+            get_price(&prices, &generic_data(&[1, 2, 3], 2), &clock()).unwrap_err(),
+            ScopeError::MedianOfQuorumNotMet
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_unset_sentinel() {
+        assert!(validate_mapping_cfg(None, &generic_data(&[1, 2], 1)).is_ok());
+
+        let mut data = MedianOfData::from_generic_data(&generic_data(&[1, 2], 1)).unwrap();
+        data.source_entries[4] = MAX_ENTRIES_U16;
+        assert_eq!(
+            validate_mapping_cfg(None, &data.to_generic_data()).unwrap_err(),
+            ScopeError::CompositeOracleInvalidSourceIndex
+        );
+
+        // Zero padded generic data is not silently read as entry 0
+        assert!(validate_mapping_cfg(None, &[0; 20]).is_err());
+    }
+}
@@ -12,6 +12,7 @@ pub mod fixed_price;
 pub mod flashtrade_lp;
 pub mod jito_restaking;
 pub mod jupiter_lp;
+pub mod median_of;
 pub mod meteora_dlmm;
 pub mod most_recent_of;
 pub mod msol_stake;
@@ -40,8 +41,8 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "yvaults")]
 use self::ktokens_token_x::TokenTypes;
 use crate::{
-    states::{DatedPrice, EmaType, OracleMappings, OraclePrices, OracleTwaps},
-    warn, ScopeError, ScopeResult,
+    states::{DatedPrice, EmaType, OracleMappings, OraclePrices, OracleTwaps, TokenMetadata},
+    warn, ScopeError, ScopeResult, MAX_ENTRIES_U16,
 };
 
 pub fn check_context<T>(ctx: &Context<T>) -> Result<()> {
@@ -155,7 +156,7 @@ pub enum OracleType {
     RedStone = 30,
     /// Adrena's perpetual LP token price
     AdrenaLp = 31,
-    /// Securitize sacred price oracle
+    /// Securitize vault share price, valued with the RedStone price of the vault asset
     Securitize = 32,
     /// Keeps track of a source price, capping and/or flooring to given source prices
     CappedFloored = 33,
@@ -178,6 +179,9 @@ pub enum OracleType {
     CappedMostRecentOf = 39,
     ScopeTwap8h = 40,
     ScopeTwap24h = 41,
+    /// Median of up to `MEDIAN_OF_MAX_SOURCES` source prices, ignoring the sources older than a
+    /// max age, as long as a minimum number of them are fresh
+    MedianOf = 42,
 }
 
 impl OracleType {
@@ -241,7 +245,83 @@ impl OracleType {
             | OracleType::Securitize
             | OracleType::CappedFloored
             | OracleType::FlashtradeLp
-            | OracleType::CappedMostRecentOf => false,
+            | OracleType::CappedMostRecentOf
+            | OracleType::MedianOf => false,
+        }
+    }
+
+    /// Whether the price of this type is refreshed with `refresh_price_list`, as opposed to
+    /// types pushed by their own refresh instruction (Chainlink, Pyth Lazer)
+    pub fn is_refreshed_by_list(self) -> bool {
+        !self.is_chainlink_provider() && self != OracleType::PythLazer
+    }
+
+    /// Whether the price of this type is computed from other entries only, without any account,
+    /// and can be recomputed when its sources are refreshed
+    pub fn is_derived_without_accounts(self) -> bool {
+        matches!(
+            self,
+            OracleType::MostRecentOf
+                | OracleType::CappedFloored
+                | OracleType::CappedMostRecentOf
+                | OracleType::MedianOf
+                | OracleType::ScopeTwap1h
+                | OracleType::ScopeTwap8h
+                | OracleType::ScopeTwap24h
+        )
+    }
+
+    /// Whether a refreshed price of this type is rejected when it is older than the stored one.
+    ///
+    /// Derived types opt out: their timestamp is computed from the clock or from other entries,
+    /// which can legitimately go backward (e.g. after a source change).
+    pub fn requires_monotonic_timestamps(self) -> bool {
+        match self {
+            OracleType::FixedPrice
+            | OracleType::DiscountToMaturity
+            | OracleType::ScopeTwap1h
+            | OracleType::ScopeTwap8h
+            | OracleType::ScopeTwap24h
+            | OracleType::MostRecentOf
+            | OracleType::CappedFloored
+            | OracleType::CappedMostRecentOf
+            | OracleType::MedianOf => false,
+
+            OracleType::SplStake
+            | OracleType::KToken
+            | OracleType::MsolStake
+            | OracleType::KTokenToTokenA
+            | OracleType::KTokenToTokenB
+            | OracleType::JupiterLpFetch
+            | OracleType::OrcaWhirlpoolAtoB
+            | OracleType::OrcaWhirlpoolBtoA
+            | OracleType::RaydiumAmmV3AtoB
+            | OracleType::RaydiumAmmV3BtoA
+            | OracleType::MeteoraDlmmAtoB
+            | OracleType::MeteoraDlmmBtoA
+            | OracleType::PythPull
+            | OracleType::PythPullEMA
+            | OracleType::SwitchboardOnDemand
+            | OracleType::JitoRestaking
+            | OracleType::Chainlink
+            | OracleType::ChainlinkRWA
+            | OracleType::ChainlinkNAV
+            | OracleType::ChainlinkX
+            | OracleType::ChainlinkExchangeRate
+            | OracleType::PythLazer
+            | OracleType::RedStone
+            | OracleType::AdrenaLp
+            | OracleType::Securitize
+            | OracleType::FlashtradeLp => true,
+
+            OracleType::Unused
+            | OracleType::DeprecatedPlaceholder1
+            | OracleType::DeprecatedPlaceholder2
+            | OracleType::DeprecatedPlaceholder3
+            | OracleType::DeprecatedPlaceholder4
+            | OracleType::DeprecatedPlaceholder5
+            | OracleType::DeprecatedPlaceholder6
+            | OracleType::DeprecatedPlaceholder7 => false,
         }
     }
 
@@ -273,6 +353,7 @@ impl OracleType {
             | OracleType::ChainlinkExchangeRate => 0,
             OracleType::MostRecentOf => 35_000,
             OracleType::CappedMostRecentOf => 40_000,
+            OracleType::MedianOf => 40_000,
             OracleType::RedStone => 20_000,
             // PythLazer oracle is not updated through normal refresh ixs
             OracleType::PythLazer => 0,
@@ -308,17 +389,95 @@ pub fn get_non_zero_price<'a, 'b>(
     oracle_twaps: &OracleTwaps,
     oracle_mappings: &OracleMappings,
     oracle_prices: &AccountLoader<OraclePrices>,
+    token_metadata: &TokenMetadata,
     index: usize,
 ) -> crate::Result<DatedPrice>
+where
+    'a: 'b,
+{
+    get_non_zero_price_with_generic_data(
+        price_type,
+        base_account,
+        extra_accounts,
+        clock,
+        oracle_twaps,
+        oracle_mappings,
+        oracle_prices,
+        token_metadata,
+        &oracle_mappings.generic[index],
+        index,
+        true,
+    )
+}
+
+/// Number of extra accounts following the price account of an entry of type `price_type` in the
+/// refresh instructions, see [`get_non_zero_price`]
+pub fn extra_accounts_count(price_type: OracleType, generic_data: &[u8; 20]) -> usize {
+    match price_type {
+        OracleType::KToken | OracleType::KTokenToTokenA | OracleType::KTokenToTokenB => 5,
+        OracleType::JupiterLpFetch => 1,
+        OracleType::OrcaWhirlpoolAtoB
+        | OracleType::OrcaWhirlpoolBtoA
+        | OracleType::MeteoraDlmmAtoB
+        | OracleType::MeteoraDlmmBtoA => 2,
+        OracleType::Securitize => securitize::SecuritizeData::from_generic_data(generic_data)
+            .map_or(0, |data| data.extra_accounts_count()),
+        _ => 0,
+    }
+}
+
+/// Consume the extra accounts of an entry that is not refreshed, so that the next entries
+/// find their accounts
+pub fn skip_extra_accounts<T>(
+    extra_accounts: &mut impl Iterator<Item = T>,
+    price_type: OracleType,
+    generic_data: &[u8; 20],
+) {
+    extra_accounts
+        .by_ref()
+        .take(extra_accounts_count(price_type, generic_data))
+        .for_each(drop);
+}
+
+/// Same as [`get_non_zero_price`] but with the given `generic_data` instead of the one stored
+/// in the mapping of the entry, to evaluate a configuration before applying it.
+///
+/// `compare_to_stored_price` must be false when the stored price comes from another oracle
+/// type or price account, so that it is not used to reject the new price.
+#[allow(clippy::too_many_arguments)]
+pub fn get_non_zero_price_with_generic_data<'a, 'b>(
+    price_type: OracleType,
+    base_account: &AccountInfo<'a>,
+    extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    clock: &Clock,
+    oracle_twaps: &OracleTwaps,
+    oracle_mappings: &OracleMappings,
+    oracle_prices: &AccountLoader<OraclePrices>,
+    token_metadata: &TokenMetadata,
+    generic_data: &[u8; 20],
+    index: usize,
+    compare_to_stored_price: bool,
+) -> crate::Result<DatedPrice>
 where
     'a: 'b,
 {
     let price = match price_type {
-        OracleType::PythPull => pyth_pull::get_price(base_account, clock),
-        OracleType::PythPullEMA => pyth_pull_ema::get_price(base_account, clock),
-        OracleType::SwitchboardOnDemand => {
-            switchboard_on_demand::get_price(base_account, clock).map_err(Into::into)
-        }
+        OracleType::PythPull => pyth_pull::get_price(
+            base_account,
+            clock,
+            token_metadata.oracle_confidence_factor(),
+        ),
+        OracleType::PythPullEMA => pyth_pull_ema::get_price(
+            base_account,
+            clock,
+            token_metadata.oracle_confidence_factor(),
+        ),
+        OracleType::SwitchboardOnDemand => switchboard_on_demand::get_price(
+            base_account,
+            clock,
+            token_metadata.oracle_confidence_factor(),
+        )
+        .map_err(Into::into),
         OracleType::SplStake => spl_stake::get_price(base_account, clock),
         #[cfg(not(feature = "yvaults"))]
         OracleType::KToken => {
@@ -396,7 +555,7 @@ where
             meteora_dlmm::get_price(false, base_account, clock, extra_accounts)
         }
         OracleType::FixedPrice => {
-            let price = fixed_price::parse_generic_data(&oracle_mappings.generic[index])?;
+            let price = fixed_price::parse_generic_data(generic_data)?;
             Ok(DatedPrice {
                 price,
                 last_updated_slot: clock.slot,
@@ -415,15 +574,11 @@ where
             msg!("Chainlink oracle type cannot be refreshed directly");
             return err!(ScopeError::PriceNotValid);
         }
-        OracleType::DiscountToMaturity => {
-            discount_to_maturity::get_price(&oracle_mappings.generic[index], clock)
+        OracleType::DiscountToMaturity => discount_to_maturity::get_price(generic_data, clock),
+        OracleType::MostRecentOf => {
+            most_recent_of::get_price(oracle_prices.load()?.deref(), generic_data, clock)
+                .map_err(Into::into)
         }
-        OracleType::MostRecentOf => most_recent_of::get_price(
-            oracle_prices.load()?.deref(),
-            &oracle_mappings.generic[index],
-            clock,
-        )
-        .map_err(Into::into),
         OracleType::RedStone => {
             let oracle_prices = oracle_prices.load()?;
             let dated_price = oracle_prices.prices[index];
@@ -433,22 +588,29 @@ where
             msg!("PythLazer oracle type cannot be refreshed directly");
             return err!(ScopeError::PriceNotValid);
         }
-        OracleType::CappedFloored => capped_floored::get_price(
-            oracle_prices.load()?.deref(),
-            &oracle_mappings.generic[index],
-        )
-        .map_err(Into::into),
-        OracleType::CappedMostRecentOf => capped_most_recent_of::get_price(
-            oracle_prices.load()?.deref(),
-            &oracle_mappings.generic[index],
-            clock,
-        )
-        .map_err(Into::into),
+        OracleType::CappedFloored => {
+            capped_floored::get_price(oracle_prices.load()?.deref(), generic_data)
+                .map_err(Into::into)
+        }
+        OracleType::CappedMostRecentOf => {
+            capped_most_recent_of::get_price(oracle_prices.load()?.deref(), generic_data, clock)
+                .map_err(Into::into)
+        }
+        OracleType::MedianOf => {
+            median_of::get_price(oracle_prices.load()?.deref(), generic_data, clock)
+                .map_err(Into::into)
+        }
         OracleType::Securitize => {
             let oracle_prices = oracle_prices.load()?;
             let dated_price = oracle_prices.prices[index];
-            securitize::get_sacred_price(base_account, &dated_price, clock, extra_accounts)
-                .map_err(Into::into)
+            securitize::get_price(
+                base_account,
+                generic_data,
+                oracle_mappings,
+                &dated_price,
+                clock,
+                extra_accounts,
+            )
         }
         OracleType::Unused
         | OracleType::DeprecatedPlaceholder1
@@ -469,9 +631,44 @@ where
         warn!("Price is 0 (token {index}, type {price_type:?}): {price:?}",);
         return err!(ScopeError::PriceNotValid);
     }
+    // Reject prices older than the configured maximum age of the source
+    let now: u64 = clock.unix_timestamp.try_into().unwrap();
+    if is_older_than_max_source_age(&price, token_metadata.max_source_age_s, now) {
+        warn!(
+            "Price is too old (token {index}, type {price_type:?}): age {}s, max {}s",
+            now.saturating_sub(price.unix_timestamp),
+            token_metadata.max_source_age_s,
+        );
+        return err!(ScopeError::SourcePriceTooOld);
+    }
+    // Never roll a price backward: the price is outdated as soon as its timestamp or its slot is
+    // older than the stored one.
+    if compare_to_stored_price && price_type.requires_monotonic_timestamps() {
+        let stored_price = oracle_prices.load()?.prices[index];
+        if is_older_than_stored(&price, &stored_price) {
+            warn!(
+                "Price is older than the stored one (token {index}, type {price_type:?}): new ts {} slot {}, stored ts {} slot {}",
+                price.unix_timestamp,
+                price.last_updated_slot,
+                stored_price.unix_timestamp,
+                stored_price.last_updated_slot,
+            );
+            return err!(ScopeError::BadTimestamp);
+        }
+    }
     Ok(price)
 }
 
+fn is_older_than_stored(price: &DatedPrice, stored_price: &DatedPrice) -> bool {
+    price.unix_timestamp < stored_price.unix_timestamp
+        || price.last_updated_slot < stored_price.last_updated_slot
+}
+
+/// A `max_source_age_s` of 0 disables the check
+fn is_older_than_max_source_age(price: &DatedPrice, max_source_age_s: u64, now: u64) -> bool {
+    max_source_age_s != 0 && now.saturating_sub(price.unix_timestamp) > max_source_age_s
+}
+
 /// Validate the given account as being an appropriate price account for the
 /// given oracle type.
 ///
@@ -494,14 +691,23 @@ pub fn validate_oracle_cfg(
         OracleType::SwitchboardOnDemand => {
             switchboard_on_demand::validate_price_account(price_account)
         }
-        OracleType::SplStake => Ok(()),
-        OracleType::KToken => Ok(()), // TODO, should validate ownership of the ktoken account
-        OracleType::KTokenToTokenA => Ok(()), // TODO, should validate ownership of the ktoken account
-        OracleType::KTokenToTokenB => Ok(()), // TODO, should validate ownership of the ktoken account
-        OracleType::MsolStake => Ok(()),
+        OracleType::SplStake => spl_stake::validate_stake_pool_account(price_account),
+        #[cfg(feature = "yvaults")]
+        OracleType::KToken | OracleType::KTokenToTokenA | OracleType::KTokenToTokenB => {
+            ktokens::validate_strategy_account(price_account).map_err(Into::into)
+        }
+        #[cfg(not(feature = "yvaults"))]
+        OracleType::KToken | OracleType::KTokenToTokenA | OracleType::KTokenToTokenB => {
+            warn!("yvaults feature is not enabled, KToken oracle type is not available");
+            err!(ScopeError::BadTokenType)
+        }
+        OracleType::MsolStake => {
+            msol_stake::validate_state_account(price_account).map_err(Into::into)
+        }
         OracleType::JupiterLpFetch => jupiter_lp::validate_jlp_pool(price_account),
         OracleType::ScopeTwap1h | OracleType::ScopeTwap8h | OracleType::ScopeTwap24h => {
-            panic!("ScopeTwap validation uses a different path")
+            warn!("ScopeTwap validation uses a different path");
+            err!(ScopeError::BadTokenType)
         }
         OracleType::OrcaWhirlpoolAtoB | OracleType::OrcaWhirlpoolBtoA => {
             orca_whirlpool::validate_pool_account(price_account)
@@ -547,7 +753,10 @@ pub fn validate_oracle_cfg(
             capped_most_recent_of::validate_mapping_cfg(price_account, generic_data)
                 .map_err(Into::into)
         }
-        OracleType::Securitize => Ok(()),
+        OracleType::MedianOf => {
+            median_of::validate_mapping_cfg(price_account, generic_data).map_err(Into::into)
+        }
+        OracleType::Securitize => securitize::validate_mapping_cfg(price_account, generic_data),
         OracleType::Unused
         | OracleType::DeprecatedPlaceholder1
         | OracleType::DeprecatedPlaceholder2
@@ -556,7 +765,8 @@ pub fn validate_oracle_cfg(
         | OracleType::DeprecatedPlaceholder5
         | OracleType::DeprecatedPlaceholder6
         | OracleType::DeprecatedPlaceholder7 => {
-            panic!("DeprecatedPlaceholder is not a valid oracle type")
+            warn!("{price_type:?} is not a valid oracle type");
+            err!(ScopeError::BadTokenType)
         }
         OracleType::AdrenaLp => adrena_lp::validate_adrena_pool(price_account, clock),
         OracleType::FlashtradeLp => flashtrade_lp::validate_flashtrade_pool(price_account, clock),
@@ -586,7 +796,6 @@ pub fn update_generic_data_must_reset_price(price_type: OracleType) -> bool {
         | OracleType::JitoRestaking
         | OracleType::RedStone
         | OracleType::AdrenaLp
-        | OracleType::Securitize
         | OracleType::ChainlinkNAV
         | OracleType::FlashtradeLp
         | OracleType::ChainlinkExchangeRate => false,
@@ -596,10 +805,12 @@ pub fn update_generic_data_must_reset_price(price_type: OracleType) -> bool {
         | OracleType::MostRecentOf
         | OracleType::CappedFloored
         | OracleType::CappedMostRecentOf
+        | OracleType::MedianOf
         | OracleType::Chainlink
         | OracleType::ChainlinkRWA
         | OracleType::ChainlinkX
-        | OracleType::PythLazer => true,
+        | OracleType::PythLazer
+        | OracleType::Securitize => true,
 
         OracleType::Unused
         | OracleType::DeprecatedPlaceholder1
@@ -612,6 +823,100 @@ pub fn update_generic_data_must_reset_price(price_type: OracleType) -> bool {
     }
 }
 
+/// Entries read by a composite oracle of type `price_type`, as configured in its generic data.
+///
+/// TWAP sources are stored in the mapping and not in the generic data, see
+/// [`OracleMappings::get_twap_source`].
+pub fn get_composite_source_entries(
+    price_type: OracleType,
+    generic_data: &[u8; 20],
+) -> ScopeResult<Vec<u16>> {
+    let entries = match price_type {
+        OracleType::MostRecentOf => {
+            most_recent_of::MostRecentOfData::from_generic_data(generic_data)?
+                .source_entries
+                .to_vec()
+        }
+        OracleType::CappedMostRecentOf => {
+            let data =
+                capped_most_recent_of::CappedMostRecentOfData::from_generic_data(generic_data)?;
+            let mut entries = data.source_entries.to_vec();
+            entries.push(data.cap_entry);
+            entries
+        }
+        OracleType::CappedFloored => {
+            let data = capped_floored::CappedFlooredData::from_generic_data(generic_data)?;
+            [Some(data.source_entry), data.cap_entry, data.floor_entry]
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+        OracleType::MedianOf => median_of::MedianOfData::from_generic_data(generic_data)?
+            .source_entries
+            .to_vec(),
+        // Not a price source but the RedStone feed is the price account of this entry
+        OracleType::Securitize => securitize::SecuritizeData::from_generic_data(generic_data)?
+            .redstone_entry
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+    // Unused slots are out of range
+    Ok(entries
+        .into_iter()
+        .filter(|&entry| entry < MAX_ENTRIES_U16)
+        .collect())
+}
+
+/// Generic data of a composite oracle of type `price_type` with its source entries rewritten
+/// by `remap_entry`. The generic data of other oracle types is returned as is.
+pub fn remap_composite_source_entries(
+    price_type: OracleType,
+    generic_data: &[u8; 20],
+    remap_entry: impl Fn(u16) -> u16,
+) -> ScopeResult<[u8; 20]> {
+    // Unused slots are out of range and kept as is
+    let remap = |entry: u16| {
+        if entry < MAX_ENTRIES_U16 {
+            remap_entry(entry)
+        } else {
+            entry
+        }
+    };
+    match price_type {
+        OracleType::MostRecentOf => {
+            let mut data = most_recent_of::MostRecentOfData::from_generic_data(generic_data)?;
+            data.source_entries = data.source_entries.map(remap);
+            Ok(data.to_generic_data())
+        }
+        OracleType::CappedMostRecentOf => {
+            let mut data =
+                capped_most_recent_of::CappedMostRecentOfData::from_generic_data(generic_data)?;
+            data.source_entries = data.source_entries.map(remap);
+            data.cap_entry = remap(data.cap_entry);
+            Ok(data.to_generic_data())
+        }
+        OracleType::CappedFloored => {
+            let mut data = capped_floored::CappedFlooredData::from_generic_data(generic_data)?;
+            data.source_entry = remap(data.source_entry);
+            data.cap_entry = data.cap_entry.map(remap);
+            data.floor_entry = data.floor_entry.map(remap);
+            Ok(data.to_generic_data())
+        }
+        OracleType::MedianOf => {
+            let mut data = median_of::MedianOfData::from_generic_data(generic_data)?;
+            data.source_entries = data.source_entries.map(remap);
+            Ok(data.to_generic_data())
+        }
+        OracleType::Securitize => {
+            let mut data = securitize::SecuritizeData::from_generic_data(generic_data)?;
+            data.redstone_entry = data.redstone_entry.map(remap);
+            Ok(data.to_generic_data())
+        }
+        _ => Ok(*generic_data),
+    }
+}
+
 pub fn debug_format_generic_data(
     d: &mut DebugStruct<'_, '_>,
     price_type: OracleType,
@@ -635,7 +940,6 @@ pub fn debug_format_generic_data(
         | OracleType::SwitchboardOnDemand
         | OracleType::JitoRestaking
         | OracleType::RedStone
-        | OracleType::Securitize
         | OracleType::AdrenaLp
         | OracleType::FlashtradeLp
         | OracleType::ScopeTwap1h
@@ -683,6 +987,12 @@ pub fn debug_format_generic_data(
                 &most_recent_of::MostRecentOfData::from_generic_data(generic_data).ok(),
             );
         }
+        OracleType::Securitize => {
+            d.field(
+                "securitize_cfg",
+                &securitize::SecuritizeData::from_generic_data(generic_data).ok(),
+            );
+        }
         OracleType::PythLazer => {
             d.field(
                 "pyth_lazer_cfg",
@@ -702,5 +1012,119 @@ pub fn debug_format_generic_data(
                     .ok(),
             );
         }
+        OracleType::MedianOf => {
+            d.field(
+                "median_of_cfg",
+                &median_of::MedianOfData::from_generic_data(generic_data).ok(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Price;
+
+    fn dated_price(unix_timestamp: u64, last_updated_slot: u64) -> DatedPrice {
+        DatedPrice {
+            price: Price { value: 1, exp: 0 },
+            last_updated_slot,
+            unix_timestamp,
+            generic_data: [0; 24],
+        }
+    }
+
+    #[test]
+    fn test_price_older_than_stored() {
+        let stored = dated_price(100, 1000);
+        assert!(!is_older_than_stored(&dated_price(100, 1000), &stored));
+        assert!(!is_older_than_stored(&dated_price(101, 1001), &stored));
+        assert!(is_older_than_stored(&dated_price(99, 1001), &stored));
+        assert!(is_older_than_stored(&dated_price(101, 999), &stored));
+        assert!(is_older_than_stored(&dated_price(99, 999), &stored));
+    }
+
+    #[test]
+    fn test_price_older_than_max_source_age() {
+        let price = dated_price(100, 1000);
+        assert!(!is_older_than_max_source_age(&price, 60, 160));
+        assert!(is_older_than_max_source_age(&price, 60, 161));
+        // Disabled check and prices from the future
+        assert!(!is_older_than_max_source_age(&price, 0, 10_000));
+        assert!(!is_older_than_max_source_age(&price, 60, 50));
+    }
+
+    #[test]
+    fn test_skip_extra_accounts_of_paused_entry() {
+        // A paused Orca entry (price account already consumed) followed by a Pyth entry
+        let mut accounts = ["orca_mint_a", "orca_mint_b", "pyth_price"].into_iter();
+        skip_extra_accounts(&mut accounts, OracleType::OrcaWhirlpoolAtoB, &[0; 20]);
+        assert_eq!(accounts.next(), Some("pyth_price"));
+
+        let mut accounts = ["pyth_price"].into_iter();
+        skip_extra_accounts(&mut accounts, OracleType::PythPull, &[0; 20]);
+        assert_eq!(accounts.next(), Some("pyth_price"));
+
+        assert_eq!(extra_accounts_count(OracleType::KToken, &[0; 20]), 5);
+        assert_eq!(extra_accounts_count(OracleType::Securitize, &[0; 20]), 3);
+    }
+
+    #[test]
+    fn test_price_with_generic_data_ignores_the_mapping() {
+        use anchor_lang::Discriminator;
+
+        let clock = Clock {
+            slot: 10,
+            unix_timestamp: 100,
+            ..Default::default()
+        };
+        let oracle_mappings: Box<OracleMappings> = Box::new(bytemuck::Zeroable::zeroed());
+        let oracle_twaps: Box<OracleTwaps> = Box::new(bytemuck::Zeroable::zeroed());
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = OraclePrices::discriminator().to_vec();
+        data.resize(8 + std::mem::size_of::<OraclePrices>(), 0);
+        let oracle_prices_account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+        let oracle_prices = AccountLoader::try_from(&oracle_prices_account).unwrap();
+
+        let mut generic_data = [0; 20];
+        Price { value: 5, exp: 1 }
+            .serialize(&mut &mut generic_data[..])
+            .unwrap();
+        let price = get_non_zero_price_with_generic_data(
+            OracleType::FixedPrice,
+            &oracle_prices_account,
+            &mut std::iter::empty::<&AccountInfo>(),
+            &clock,
+            &oracle_twaps,
+            &oracle_mappings,
+            &oracle_prices,
+            &TokenMetadata::default(),
+            &generic_data,
+            0,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!((price.price.value, price.price.exp), (5, 1));
+        assert_eq!((price.last_updated_slot, price.unix_timestamp), (10, 100));
+    }
+
+    #[test]
+    fn test_validate_oracle_cfg_rejects_invalid_types() {
+        let clock = Clock::default();
+        for price_type in [OracleType::ScopeTwap1h, OracleType::DeprecatedPlaceholder1] {
+            assert!(validate_oracle_cfg(price_type, None, &[0; 20], &clock).is_err());
+        }
     }
 }
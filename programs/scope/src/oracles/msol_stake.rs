@@ -1,21 +1,21 @@
 use anchor_lang::prelude::*;
-use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::{borsh0_10::try_from_slice_unchecked, pubkey};
 
 use self::msol_stake_pool::State;
-use crate::{warn, DatedPrice, Price, ScopeError, ScopeResult};
+use crate::{utils::check_account_owner, warn, DatedPrice, Price, ScopeError, ScopeResult};
 
 const DECIMALS: u32 = 15u32;
 
+pub const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+/// Anchor discriminator of the Marinade `State` account
+const STATE_DISCRIMINATOR: [u8; 8] = [216, 146, 107, 94, 104, 75, 182, 177];
+
 // Gives the price of 1 staked SOL in SOL
 pub fn get_price(
     msol_pool_account_info: &AccountInfo,
     current_clock: &Clock,
 ) -> ScopeResult<DatedPrice> {
-    let stake_pool = try_from_slice_unchecked::<State>(&msol_pool_account_info.data.borrow()[8..])
-        .map_err(|_| {
-            warn!("Provided pubkey is not a valid MSOL Stake account");
-            ScopeError::UnexpectedAccount
-        })?;
+    let stake_pool = load_state(msol_pool_account_info)?;
 
     let value = scaled_rate(&stake_pool).map_err(|e| {
         warn!("Error while calculating the scaled rate: {:?}", e);
@@ -36,6 +36,34 @@ pub fn get_price(
     Ok(dated_price)
 }
 
+pub fn validate_state_account(msol_pool_account_info: Option<&AccountInfo>) -> ScopeResult<()> {
+    let Some(msol_pool_account_info) = msol_pool_account_info else {
+        warn!("No Marinade state account provided");
+        return Err(ScopeError::ExpectedPriceAccount);
+    };
+    let _ = load_state(msol_pool_account_info)?;
+    Ok(())
+}
+
+fn load_state(msol_pool_account_info: &AccountInfo) -> ScopeResult<State> {
+    check_account_owner(msol_pool_account_info, &[MARINADE_PROGRAM_ID])?;
+    let data = msol_pool_account_info.data.borrow();
+    let discriminator = data
+        .get(..8)
+        .ok_or(ScopeError::UnableToDeserializeAccount)?;
+    if discriminator != STATE_DISCRIMINATOR {
+        warn!(
+            "Provided Marinade account {} is not a State account",
+            msol_pool_account_info.key
+        );
+        return Err(ScopeError::InvalidAccountDiscriminator);
+    }
+    try_from_slice_unchecked::<State>(&data[8..]).map_err(|_| {
+        warn!("Provided pubkey is not a valid MSOL Stake account");
+        ScopeError::UnexpectedAccount
+    })
+}
+
 fn scaled_rate(stake_pool: &State) -> ScopeResult<u64> {
     const FACTOR: u64 = 10u64.pow(DECIMALS);
     stake_pool.calc_lamports_from_msol_amount(FACTOR)
@@ -53,11 +53,13 @@ pub fn validate_payload_data_for_group(
     Ok(())
 }
 
+/// Validate the feed at `feed_idx` and return its price along with the bid/ask spread,
+/// used as the confidence interval of the price (same exponent as the price)
 pub fn validate_payload_data_for_token(
     payload_data: &PayloadData,
     feed_idx: usize,
     pyth_lazer_data: &PythLazerData,
-) -> ScopeResult<Price> {
+) -> ScopeResult<(Price, u64)> {
     let PythLazerData {
         feed_id: expected_feed_id,
         exponent: expected_exponent,
@@ -148,15 +150,15 @@ pub fn validate_payload_data_for_token(
         exp: exponent_u64,
     };
 
-    let spread_value_opt = best_ask_price.value.checked_sub(best_bid_price.value);
-    if spread_value_opt.is_none() {
-        return Err(ScopeError::PythLazerInvalidAskBidPrices);
-    }
+    let spread_value = best_ask_price
+        .value
+        .checked_sub(best_bid_price.value)
+        .ok_or(ScopeError::PythLazerInvalidAskBidPrices)?;
 
     check_confidence_interval(
         u128::from(new_price.value),
         u32::from(*expected_exponent),
-        u128::from(spread_value_opt.unwrap()),
+        u128::from(spread_value),
         u32::from(*expected_exponent),
         *confidence_factor,
     )
@@ -169,7 +171,7 @@ pub fn validate_payload_data_for_token(
         e
     })?;
 
-    Ok(new_price)
+    Ok((new_price, spread_value))
 }
 
 pub fn update_price(
@@ -193,7 +195,8 @@ pub fn update_price(
     }
 
     let pyth_lazer_data = PythLazerData::from_generic_data(generic_data)?;
-    let new_price = validate_payload_data_for_token(data, feed_idx, &pyth_lazer_data)?;
+    let (new_price, confidence) =
+        validate_payload_data_for_token(data, feed_idx, &pyth_lazer_data)?;
 
     let current_onchain_timestamp_s: u64 = clock
         .unix_timestamp
@@ -213,6 +216,7 @@ pub fn update_price(
         unix_timestamp: price_timestamp_s,
         generic_data,
     };
+    dated_price.set_confidence(confidence);
 
     Ok(())
 }
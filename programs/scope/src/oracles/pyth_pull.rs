@@ -12,14 +12,18 @@ pub const MAXIMUM_AGE: u64 = 10 * 60; // Ten minutes
 pub use pyth_sdk_solana::state as pyth_client;
 
 use super::pyth::validate_valid_price;
-use crate::{utils::consts::ORACLE_CONFIDENCE_FACTOR, warn};
+use crate::warn;
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    confidence_factor: u32,
+) -> Result<DatedPrice> {
     let price_account: PriceUpdateV2 = account_deserialize(price_info)?;
 
     let price = price_account.get_price_no_older_than_with_custom_verification_level(
         clock,
-        i64::MAX.try_into().unwrap(), // MAXIMUM_AGE, // this should be filtered by the caller
+        i64::MAX.try_into().unwrap(), // filtered by the caller with the entry max source age
         &price_account.price_message.feed_id,
         VerificationLevel::Full, // All our prices and the sponsored feeds are full verified
     )?;
@@ -47,7 +51,7 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         price,
         publish_time,
     };
-    let price = validate_valid_price(&old_pyth_price, ORACLE_CONFIDENCE_FACTOR).map_err(|e| {
+    let price = validate_valid_price(&old_pyth_price, confidence_factor).map_err(|e| {
         warn!(
             "Confidence interval check failed on pyth account {}",
             price_info.key
@@ -60,12 +64,15 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
 
     // todo: Discuss how we should handle the time jump that can happen when there is an outage?
     let last_updated_slot = estimate_slot_update_from_ts(clock, unix_timestamp);
-    Ok(DatedPrice {
+    let mut dated_price = DatedPrice {
         price,
         last_updated_slot,
         unix_timestamp,
         ..Default::default()
-    })
+    };
+    // Pyth confidence is expressed with the same exponent as the price
+    dated_price.set_confidence(conf);
+    Ok(dated_price)
 }
 
 pub fn validate_price_update_v2_info(price_info: Option<&AccountInfo>) -> Result<()> {
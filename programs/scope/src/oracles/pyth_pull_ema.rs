@@ -15,9 +15,13 @@ pub const MAXIMUM_AGE: u64 = 10 * 60; // Ten minutes
 use pyth_sdk_solana::Price as PythPrice;
 
 use super::pyth::validate_valid_price;
-use crate::{utils::consts::ORACLE_CONFIDENCE_FACTOR, warn};
+use crate::warn;
 
-pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice> {
+pub fn get_price(
+    price_info: &AccountInfo,
+    clock: &Clock,
+    confidence_factor: u32,
+) -> Result<DatedPrice> {
     let price_account: PriceUpdateV2 = account_deserialize(price_info)?;
     let exponent = price_account.price_message.exponent;
     let conf = price_account.price_message.conf;
@@ -40,7 +44,7 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
         price: price.price,
         publish_time,
     };
-    let price = validate_valid_price(&old_pyth_price, ORACLE_CONFIDENCE_FACTOR).map_err(|e| {
+    let price = validate_valid_price(&old_pyth_price, confidence_factor).map_err(|e| {
         warn!(
             "Confidence interval check failed on pyth account {}",
             price_info.key
@@ -53,12 +57,15 @@ pub fn get_price(price_info: &AccountInfo, clock: &Clock) -> Result<DatedPrice>
 
     // todo: Discuss how we should handle the time jump that can happen when there is an outage?
     let last_updated_slot = estimate_slot_update_from_ts(clock, unix_timestamp);
-    Ok(DatedPrice {
+    let mut dated_price = DatedPrice {
         price,
         last_updated_slot,
         unix_timestamp,
         ..Default::default()
-    })
+    };
+    // Pyth confidence is expressed with the same exponent as the price
+    dated_price.set_confidence(conf);
+    Ok(dated_price)
 }
 
 fn get_ema_with_custom_verification_level(
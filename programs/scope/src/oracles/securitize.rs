@@ -1,31 +1,99 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{token::spl_token::state::Mint, token_interface::TokenAccount};
+use anchor_spl::{
+    token::spl_token::{self, state::Mint},
+    token_2022::spl_token_2022,
+    token_interface::TokenAccount,
+};
 use securitize_itf::accounts::VaultState;
 use solana_program::{program_pack::Pack, pubkey};
 
-use super::redstone;
+use super::{redstone, OracleType};
 use crate::{
-    utils::{account_deserialize, math},
-    DatedPrice, Price, ScopeError, ScopeResult,
+    states::OracleMappings,
+    utils::{account_deserialize, check_account_owner, math},
+    warn, DatedPrice, Price, ScopeError, ScopeResult, MAX_ENTRIES_U16,
 };
 
-// sAcred is not necessarily pegged 1:1 to ACRED as the vault can be undercollateralized (but it cannot overcollateralize as the vault mechanism tops the value at $1). So it may be worth $1 or less depending on the vault status.
+// A Securitize vault share (e.g. sACRED) is not necessarily pegged 1:1 to its asset (e.g. ACRED) as the vault can be undercollateralized (but it cannot overcollateralize as the vault mechanism tops the value at $1). So it may be worth $1 or less depending on the vault status.
 // The way to get the price, which is secured via RedStone, because the method does as follows:
-// Checks the amount of ACRED inside the sACRED vault
-// Multiplies that by the ACRED price from REDSTONE feed
-// Checks if that value is above or below the amount of issued sACRED tokens
+// Checks the amount of asset inside the vault
+// Multiplies that by the asset price from the REDSTONE feed
+// Checks if that value is above or below the amount of issued share tokens
 // if above, then the price is $1
-// if below then the price is the $ value in the vault (based on the REDSTONE price) divided by the amount of outstanding sACRED
+// if below then the price is the $ value in the vault (based on the REDSTONE price) divided by the amount of outstanding shares
+
+// The mapping price account is the vault state.
+// There is no connection between VaultState to the Feed Account it uses for the share value, so the
+// RedStone feed is configured in the mapping generic data, as the index of a `RedStone` entry of the
+// same feed whose price account is the RedStone feed.
 
-// Only works with the sAcred vault
-// There is no connection between VaultState to the Feed Account it uses for the share value.
-// WARNING: If this ever changes, we also need to ensure that the securitize token mint has the same number
-// of decimals as the base token mint or include this difference in the computation of the price
+/// sACRED vault and its RedStone feed, used when the generic data of an entry is not set
 pub const ACRED_VAULT_PK: Pubkey = pubkey!("9L4WxKkUHKBZ96EpHBc7APqvEhobmY1A2ENk5dUfdrpw");
 pub const REDSTONE_FEED_PK: Pubkey = pubkey!("6sK8czVw8Xy6T8YbH6VC8p5ovNZD2mXf5vUTv8sgnUJf");
 
-pub fn get_sacred_price<'a, 'b>(
+const TOKEN_PROGRAM_IDS: [Pubkey; 2] = [spl_token::ID, spl_token_2022::ID];
+
+/// First byte of the generic data of the entries configured with a RedStone entry
+const SECURITIZE_DATA_V1: u8 = 1;
+
+/// Generic data of a Securitize entry.
+///
+/// The mapping generic data is too small to hold a full pubkey, so the RedStone feed is the price
+/// account of the `RedStone` entry `redstone_entry`.
+/// Entries with a zero generic data are the sACRED entries configured before: they are priced
+/// with [`REDSTONE_FEED_PK`], assume the same decimals for the shares and the asset and do not take
+/// the asset mint as extra account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecuritizeData {
+    pub redstone_entry: Option<u16>,
+}
+
+impl SecuritizeData {
+    pub fn from_generic_data(generic_data: &[u8; 20]) -> ScopeResult<Self> {
+        if *generic_data == [0u8; 20] {
+            return Ok(Self {
+                redstone_entry: None,
+            });
+        }
+        if generic_data[0] != SECURITIZE_DATA_V1 || generic_data[3..].iter().any(|&b| b != 0) {
+            warn!("Invalid Securitize generic data {generic_data:?}");
+            return Err(ScopeError::InvalidGenericData);
+        }
+        let redstone_entry = u16::from_le_bytes([generic_data[1], generic_data[2]]);
+        if redstone_entry >= MAX_ENTRIES_U16 {
+            warn!("Invalid RedStone entry {redstone_entry} for Securitize oracle");
+            return Err(ScopeError::BadTokenNb);
+        }
+        Ok(Self {
+            redstone_entry: Some(redstone_entry),
+        })
+    }
+
+    pub fn to_generic_data(&self) -> [u8; 20] {
+        let mut generic_data = [0u8; 20];
+        if let Some(redstone_entry) = self.redstone_entry {
+            generic_data[0] = SECURITIZE_DATA_V1;
+            generic_data[1..3].copy_from_slice(&redstone_entry.to_le_bytes());
+        }
+        generic_data
+    }
+
+    /// Number of extra accounts expected after the vault state
+    pub fn extra_accounts_count(&self) -> usize {
+        if self.redstone_entry.is_some() {
+            4
+        } else {
+            3
+        }
+    }
+}
+
+/// Expects as extra accounts: the share mint, the asset vault, the RedStone feed and, unless the
+/// generic data is not set, the asset mint
+pub fn get_price<'a, 'b>(
     vault_state_account_info: &AccountInfo,
+    generic_data: &[u8; 20],
+    oracle_mappings: &OracleMappings,
     dated_price: &DatedPrice,
     clock: &Clock,
     extra_accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
@@ -33,7 +101,9 @@ pub fn get_sacred_price<'a, 'b>(
 where
     'a: 'b,
 {
+    check_account_owner(vault_state_account_info, &[securitize_itf::ID])?;
     let vault_state: VaultState = account_deserialize(vault_state_account_info)?;
+    let securitize_data = SecuritizeData::from_generic_data(generic_data)?;
 
     let mint_account = extra_accounts
         .next()
@@ -44,52 +114,124 @@ where
     let redstone_price_data = extra_accounts
         .next()
         .ok_or(ScopeError::AccountsAndTokenMismatch)?;
+    let asset_mint_account = match securitize_data.redstone_entry {
+        Some(_) => Some(
+            extra_accounts
+                .next()
+                .ok_or(ScopeError::AccountsAndTokenMismatch)?,
+        ),
+        None => None,
+    };
 
+    let redstone_feed = get_redstone_feed(
+        &securitize_data,
+        *vault_state_account_info.key,
+        oracle_mappings,
+    )?;
     check_accounts(
         &vault_state,
-        *vault_state_account_info.key,
+        redstone_feed,
         *vault_account.key,
         *mint_account.key,
         redstone_price_data,
     )?;
+    check_account_owner(mint_account, &TOKEN_PROGRAM_IDS)?;
+    check_account_owner(vault_account, &TOKEN_PROGRAM_IDS)?;
 
-    let mint = {
-        let mint_borrow = mint_account.data.borrow();
-        Mint::unpack(&mint_borrow)
-    }?;
+    let share_mint = unpack_mint(mint_account)?;
 
     let vault = {
         let vault_borrow = vault_account.data.borrow();
         TokenAccount::try_deserialize(&mut &**vault_borrow)
     }?;
 
+    // Express the vault holdings with the decimals of the shares
+    let total_assets = match asset_mint_account {
+        Some(asset_mint_account) => {
+            check_account_owner(asset_mint_account, &TOKEN_PROGRAM_IDS)?;
+            require_keys_eq!(
+                vault.mint,
+                asset_mint_account.key(),
+                ScopeError::UnexpectedAccount
+            );
+            let asset_mint = unpack_mint(asset_mint_account)?;
+            math::normalize_rate(vault.amount, asset_mint.decimals, share_mint.decimals)?
+        }
+        // sACRED shares and ACRED have the same decimals
+        None => vault.amount,
+    };
+
     let asset_dated_price = redstone::get_price(redstone_price_data, dated_price, clock)?;
 
-    let rate = get_rate(&asset_dated_price.price, &mint)?;
-    let price = get_share_value(vault.amount, rate, mint.decimals, mint.supply)?;
+    let rate = get_rate(&asset_dated_price.price, share_mint.decimals)?;
+    let price = get_share_value(total_assets, rate, share_mint.decimals, share_mint.supply)?;
 
     Ok(DatedPrice {
         price: Price {
             value: price,
-            exp: mint.decimals as u64,
+            exp: share_mint.decimals as u64,
         },
         // Reuse the timestamp and slot from the asset price
         ..asset_dated_price
     })
 }
 
+/// RedStone feed configured for the vault `vault_state_key`
+fn get_redstone_feed(
+    securitize_data: &SecuritizeData,
+    vault_state_key: Pubkey,
+    oracle_mappings: &OracleMappings,
+) -> Result<Pubkey> {
+    let Some(redstone_entry) = securitize_data.redstone_entry else {
+        // The decimals of the asset are only known for the sACRED vault
+        require_keys_eq!(
+            vault_state_key,
+            ACRED_VAULT_PK,
+            ScopeError::UnexpectedAccount
+        );
+        return Ok(REDSTONE_FEED_PK);
+    };
+    let redstone_entry = usize::from(redstone_entry);
+    if oracle_mappings.get_entry_type(redstone_entry)? != OracleType::RedStone {
+        warn!("Securitize: entry {redstone_entry} is not a RedStone entry");
+        return err!(ScopeError::BadTokenType);
+    }
+    Ok(oracle_mappings.price_info_accounts[redstone_entry])
+}
+
+pub fn validate_mapping_cfg(
+    vault_state_account_info: Option<&AccountInfo>,
+    generic_data: &[u8; 20],
+) -> Result<()> {
+    let Some(vault_state_account_info) = vault_state_account_info else {
+        warn!("No Securitize vault state account provided");
+        return err!(ScopeError::ExpectedPriceAccount);
+    };
+    check_account_owner(vault_state_account_info, &[securitize_itf::ID])?;
+    let _: VaultState = account_deserialize(vault_state_account_info)?;
+    match SecuritizeData::from_generic_data(generic_data)?.redstone_entry {
+        Some(redstone_entry) => {
+            msg!("Securitize: RedStone feed of entry {redstone_entry}");
+        }
+        None => {
+            require_keys_eq!(
+                vault_state_account_info.key(),
+                ACRED_VAULT_PK,
+                ScopeError::UnexpectedAccount
+            );
+            msg!("Securitize: no RedStone entry configured, using the sACRED feed {REDSTONE_FEED_PK}");
+        }
+    }
+    Ok(())
+}
+
 fn check_accounts(
     state: &VaultState,
-    state_account_key: Pubkey,
+    redstone_feed: Pubkey,
     vault_account_key: Pubkey,
     mint_account_key: Pubkey,
     redstone_adapter_account: &AccountInfo,
 ) -> Result<()> {
-    require_keys_eq!(
-        state_account_key,
-        ACRED_VAULT_PK,
-        ScopeError::UnexpectedAccount
-    );
     require_keys_eq!(
         state.asset_vault,
         vault_account_key,
@@ -103,17 +245,28 @@ fn check_accounts(
     require_keys_eq!(
         *redstone_adapter_account.owner,
         redstone_itf::ID,
-        ScopeError::UnexpectedAccount
-    );
-    require_keys_eq!(
-        *redstone_adapter_account.key,
-        REDSTONE_FEED_PK,
-        ScopeError::UnexpectedAccount
+        ScopeError::WrongAccountOwner
     );
+    if *redstone_adapter_account.key != redstone_feed {
+        warn!(
+            "Securitize: RedStone feed {} is not the configured one {redstone_feed}",
+            redstone_adapter_account.key
+        );
+        return err!(ScopeError::UnexpectedAccount);
+    }
 
     Ok(())
 }
 
+fn unpack_mint(mint_account: &AccountInfo) -> Result<Mint> {
+    let mint_borrow = mint_account.data.borrow();
+    let mint_data = mint_borrow
+        .get(..Mint::LEN)
+        .ok_or(ScopeError::UnableToDeserializeAccount)?;
+    Ok(Mint::unpack_from_slice(mint_data)?)
+}
+
+/// Value of one share, with `total_assets` expressed with the share `decimals`
 fn get_share_value(
     total_assets: u64,
     rate: u64,
@@ -123,15 +276,14 @@ fn get_share_value(
     if total_supply == 0 {
         return Ok(0);
     }
-    // WARNING: This assumes both token mint has the same number of decimals
     Ok(u64::min(
         10u64.pow(decimals.into()),
         math::mul_div(total_assets, rate, total_supply)?,
     ))
 }
 
-fn get_rate(price: &Price, mint: &Mint) -> ScopeResult<u64> {
-    let rate = math::normalize_rate(price.value, price.exp as u8, mint.decimals)?;
+fn get_rate(price: &Price, decimals: u8) -> ScopeResult<u64> {
+    let rate = math::normalize_rate(price.value, price.exp as u8, decimals)?;
 
     if rate == 0 {
         return Err(ScopeError::PriceNotValid);
@@ -139,3 +291,61 @@ fn get_rate(price: &Price, mint: &Mint) -> ScopeResult<u64> {
 
     Ok(rate)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_securitize_data_generic_data() {
+        let legacy = SecuritizeData::from_generic_data(&[0; 20]).unwrap();
+        assert_eq!(legacy.redstone_entry, None);
+        assert_eq!(legacy.extra_accounts_count(), 3);
+
+        let data = SecuritizeData {
+            redstone_entry: Some(300),
+        };
+        assert_eq!(
+            SecuritizeData::from_generic_data(&data.to_generic_data()).unwrap(),
+            data
+        );
+        assert_eq!(data.extra_accounts_count(), 4);
+
+        let mut generic_data = data.to_generic_data();
+        generic_data[19] = 1;
+        assert!(SecuritizeData::from_generic_data(&generic_data).is_err());
+    }
+
+    #[test]
+    fn test_redstone_feed_is_the_full_entry_price_account() {
+        let mut oracle_mappings: Box<OracleMappings> = Box::new(bytemuck::Zeroable::zeroed());
+        let feed = Pubkey::new_unique();
+        oracle_mappings.price_types[7] = OracleType::RedStone.into();
+        oracle_mappings.price_info_accounts[7] = feed;
+        oracle_mappings.price_types[8] = OracleType::PythPull.into();
+
+        let data = SecuritizeData {
+            redstone_entry: Some(7),
+        };
+        let vault = Pubkey::new_unique();
+        assert_eq!(
+            get_redstone_feed(&data, vault, &oracle_mappings).unwrap(),
+            feed
+        );
+
+        let data = SecuritizeData {
+            redstone_entry: Some(8),
+        };
+        assert!(get_redstone_feed(&data, vault, &oracle_mappings).is_err());
+
+        // The legacy configuration only prices the sACRED vault
+        let legacy = SecuritizeData {
+            redstone_entry: None,
+        };
+        assert_eq!(
+            get_redstone_feed(&legacy, ACRED_VAULT_PK, &oracle_mappings).unwrap(),
+            REDSTONE_FEED_PK
+        );
+        assert!(get_redstone_feed(&legacy, vault, &oracle_mappings).is_err());
+    }
+}
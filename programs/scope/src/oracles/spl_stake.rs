@@ -1,11 +1,21 @@
 use anchor_lang::prelude::*;
-use solana_program::borsh0_10::try_from_slice_unchecked;
+use solana_program::{borsh0_10::try_from_slice_unchecked, pubkey};
 
-use self::spl_stake_pool::StakePool;
-use crate::{utils::SECONDS_PER_HOUR, warn, DatedPrice, Price, Result, ScopeError};
+use self::spl_stake_pool::{AccountType, StakePool};
+use crate::{
+    utils::{check_account_owner, SECONDS_PER_HOUR},
+    warn, DatedPrice, Price, Result, ScopeError,
+};
 
 const DECIMALS: u32 = 15u32;
 
+/// Programs running SPL stake pools: the SPL stake pool program and the Sanctum deployments
+pub const STAKE_POOL_PROGRAM_IDS: [Pubkey; 3] = [
+    pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
+    pubkey!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY"),
+    pubkey!("SPMBzsVUuoHA4Jm6KunbsotaahvVikZs1JyTW6iJvbn"),
+];
+
 /// 0.6%
 const MAX_ACCEPTABLE_FEE_BPS: spl_stake_pool::Fee = spl_stake_pool::Fee {
     denominator: 1000,
@@ -17,11 +27,7 @@ pub fn get_price(
     stake_pool_account_info: &AccountInfo,
     current_clock: &Clock,
 ) -> Result<DatedPrice> {
-    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account_info.data.borrow())
-        .map_err(|_| {
-            warn!("Provided pubkey is not a SPL Stake account");
-            ScopeError::UnexpectedAccount
-        })?;
+    let stake_pool = load_stake_pool(stake_pool_account_info)?;
 
     #[cfg(not(feature = "skip_price_validation"))]
     {
@@ -65,6 +71,32 @@ pub fn get_price(
     Ok(dated_price)
 }
 
+pub fn validate_stake_pool_account(stake_pool_account_info: Option<&AccountInfo>) -> Result<()> {
+    let Some(stake_pool_account_info) = stake_pool_account_info else {
+        warn!("No SPL stake pool account provided");
+        return err!(ScopeError::ExpectedPriceAccount);
+    };
+    let _ = load_stake_pool(stake_pool_account_info)?;
+    Ok(())
+}
+
+fn load_stake_pool(stake_pool_account_info: &AccountInfo) -> Result<StakePool> {
+    check_account_owner(stake_pool_account_info, &STAKE_POOL_PROGRAM_IDS)?;
+    let stake_pool = try_from_slice_unchecked::<StakePool>(&stake_pool_account_info.data.borrow())
+        .map_err(|_| {
+            warn!("Provided pubkey is not a SPL Stake account");
+            ScopeError::UnexpectedAccount
+        })?;
+    if stake_pool.account_type != AccountType::StakePool {
+        warn!(
+            "Provided SPL Stake account has type {:?} instead of StakePool",
+            stake_pool.account_type
+        );
+        return err!(ScopeError::InvalidAccountDiscriminator);
+    }
+    Ok(stake_pool)
+}
+
 fn scaled_rate(stake_pool: &StakePool) -> Result<u64> {
     const FACTOR: u64 = 10u64.pow(DECIMALS);
     stake_pool
@@ -325,3 +357,54 @@ mod spl_stake_pool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::error_code;
+
+    fn validate(owner: &Pubkey, account_type: AccountType) -> Result<()> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = StakePool {
+            account_type,
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            0,
+        );
+        validate_stake_pool_account(Some(&account))
+    }
+
+    fn assert_scope_error(result: Result<()>, expected: ScopeError) {
+        assert_eq!(error_code(&result.unwrap_err()), Some(expected.into()));
+    }
+
+    #[test]
+    fn test_validate_stake_pool_account() {
+        for owner in STAKE_POOL_PROGRAM_IDS.iter() {
+            assert!(validate(owner, AccountType::StakePool).is_ok());
+        }
+        assert_scope_error(
+            validate(&Pubkey::new_unique(), AccountType::StakePool),
+            ScopeError::WrongAccountOwner,
+        );
+        assert_scope_error(
+            validate(&STAKE_POOL_PROGRAM_IDS[0], AccountType::ValidatorList),
+            ScopeError::InvalidAccountDiscriminator,
+        );
+        assert_scope_error(
+            validate_stake_pool_account(None),
+            ScopeError::ExpectedPriceAccount,
+        );
+    }
+}
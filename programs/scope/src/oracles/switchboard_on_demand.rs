@@ -5,8 +5,7 @@ use sbod_itf::accounts::PullFeedAccountData;
 
 use crate::{
     utils::{
-        consts::ORACLE_CONFIDENCE_FACTOR,
-        math::{check_confidence_interval, slots_to_secs},
+        math::{check_confidence_interval, slots_to_secs, ten_pow},
         zero_copy_deserialize,
     },
     warn, DatedPrice, Price, ScopeError,
@@ -17,6 +16,7 @@ const MAX_EXPONENT: u32 = 15;
 pub fn get_price(
     switchboard_feed_info: &AccountInfo,
     clock: &Clock,
+    confidence_factor: u32,
 ) -> std::result::Result<DatedPrice, ScopeError> {
     let feed = zero_copy_deserialize::<PullFeedAccountData>(switchboard_feed_info)?;
 
@@ -26,16 +26,18 @@ pub fn get_price(
         .ok_or(ScopeError::SwitchboardOnDemandError)?;
     let price: Price = price_switchboard_desc.try_into()?;
 
+    let std_dev = feed
+        .result
+        .std_dev()
+        .ok_or(ScopeError::SwitchboardOnDemandError)?;
+
     if !cfg!(feature = "skip_price_validation") {
-        let std_dev = feed
-            .result
-            .std_dev()
-            .ok_or(ScopeError::SwitchboardOnDemandError)?;
         if validate_confidence(
             price_switchboard_desc.mantissa(),
             price_switchboard_desc.scale(),
             std_dev.mantissa(),
             std_dev.scale(),
+            confidence_factor,
         )
         .is_err()
         {
@@ -60,12 +62,32 @@ pub fn get_price(
         .unwrap_or(0)
         .saturating_sub(slots_to_secs(elapsed_slots));
 
-    Ok(DatedPrice {
+    let mut dated_price = DatedPrice {
         price,
         last_updated_slot,
         unix_timestamp,
         ..Default::default()
-    })
+    };
+    dated_price.set_confidence(std_dev_to_price_exp(std_dev, price.exp)?);
+    Ok(dated_price)
+}
+
+/// Express the standard deviation with the exponent of the price, rounding up
+fn std_dev_to_price_exp(
+    std_dev: rust_decimal::Decimal,
+    price_exp: u64,
+) -> std::result::Result<u64, ScopeError> {
+    let mantissa = u128::try_from(std_dev.mantissa()).map_err(|_| ScopeError::PriceNotValid)?;
+    let std_dev_exp = std_dev.scale();
+    let price_exp: u32 = price_exp.try_into()?;
+    let value = if std_dev_exp > price_exp {
+        mantissa.div_ceil(ten_pow(std_dev_exp - price_exp))
+    } else {
+        mantissa
+            .checked_mul(ten_pow(price_exp - std_dev_exp))
+            .ok_or(ScopeError::MathOverflow)?
+    };
+    value.try_into().map_err(|_| ScopeError::IntegerOverflow)
 }
 
 #[inline(always)]
@@ -74,13 +96,14 @@ fn validate_confidence(
     price_scale: u32,
     stdev_mantissa: i128,
     stdev_scale: u32,
+    confidence_factor: u32,
 ) -> std::result::Result<(), ScopeError> {
     check_confidence_interval(
         price_mantissa.try_into().unwrap(),
         price_scale,
         stdev_mantissa.try_into().unwrap(),
         stdev_scale,
-        ORACLE_CONFIDENCE_FACTOR,
+        confidence_factor,
     )
 }
 
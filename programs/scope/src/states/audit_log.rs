@@ -0,0 +1,277 @@
+use anchor_lang::prelude::*;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use super::Configuration;
+use crate::{utils::consts::AUDIT_LOG_SIZE, ScopeError};
+
+/// Number of records kept in the audit log, older records are overwritten
+pub const AUDIT_LOG_LEN: usize = 256;
+/// Size of the old and new values of a record
+pub const AUDIT_VALUE_LEN: usize = 64;
+/// Entry id of the records that are not related to an entry
+pub const AUDIT_NO_ENTRY: u16 = u16::MAX;
+
+static_assertions::const_assert_eq!(AUDIT_LOG_SIZE, std::mem::size_of::<AuditLog>());
+static_assertions::const_assert_eq!(0, std::mem::size_of::<AuditLog>() % 8);
+// Ring buffer of the configuration changes applied to a feed
+#[account(zero_copy)]
+pub struct AuditLog {
+    pub configuration: Pubkey,
+    /// Number of records ever written, the last one is at `(records_count - 1) % AUDIT_LOG_LEN`
+    pub records_count: u64,
+    pub records: [AuditRecord; AUDIT_LOG_LEN],
+}
+
+/// A single applied change.
+///
+/// `old_value` and `new_value` hold the fields touched by the change, in the order of their
+/// declaration in the accounts, little-endian and zero-padded.
+#[zero_copy]
+#[derive(Debug)]
+pub struct AuditRecord {
+    pub slot: u64,
+    pub signer: Pubkey,
+    pub entry_id: u16,
+    /// [`AuditRecordKind`] of the change
+    pub kind: u8,
+    pub _padding: [u8; 5],
+    pub old_value: [u8; AUDIT_VALUE_LEN],
+    pub new_value: [u8; AUDIT_VALUE_LEN],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[repr(u8)]
+pub enum AuditRecordKind {
+    RemoveEntry = 0,
+    MappingConfig = 1,
+    MappingTwapEntry = 2,
+    MappingTwapEnabledBitmask = 3,
+    MappingRefPrice = 4,
+    MetadataName = 5,
+    MetadataMaxPriceAgeSlots = 6,
+    MetadataGroupIdsBitset = 7,
+    MetadataMaxPriceMove = 8,
+    MetadataConfidenceBps = 9,
+    MetadataMaxSourceAge = 10,
+    MetadataAdditionalRefPrices = 11,
+    MetadataRefPriceMaxAge = 12,
+    MoveEntry = 13,
+    CopyEntry = 14,
+    MetadataRederiveOnSourceRefresh = 15,
+    SetAdminCached = 128,
+    ApproveAdminCached = 129,
+    /// Values are the role followed by its holder
+    SetRole = 130,
+    /// Values are the pause target followed by whether it is paused
+    SetPaused = 131,
+    /// Values are the allowlist and the program followed by whether it is allowed
+    UpdateRefreshAllowlist = 132,
+    /// Values are the mapping update delay in seconds
+    SetMappingUpdateDelay = 133,
+    /// Values are the page index followed by the configuration of the page
+    SetExtensionPage = 134,
+}
+
+impl AuditLog {
+    pub fn push(
+        &mut self,
+        slot: u64,
+        signer: Pubkey,
+        entry_id: u16,
+        kind: AuditRecordKind,
+        old_value: &[u8],
+        new_value: &[u8],
+    ) {
+        let index = (self.records_count % AUDIT_LOG_LEN as u64) as usize;
+        let record = &mut self.records[index];
+        record.slot = slot;
+        record.signer = signer;
+        record.entry_id = entry_id;
+        record.kind = kind.into();
+        record.old_value = to_audit_value(old_value);
+        record.new_value = to_audit_value(new_value);
+        self.records_count += 1;
+    }
+
+    /// Records from the oldest to the most recent one
+    pub fn iter_records(&self) -> impl Iterator<Item = &AuditRecord> {
+        let count = self.records_count.min(AUDIT_LOG_LEN as u64) as usize;
+        let first = ((self.records_count - count as u64) % AUDIT_LOG_LEN as u64) as usize;
+        (0..count).map(move |i| &self.records[(first + i) % AUDIT_LOG_LEN])
+    }
+}
+
+fn to_audit_value(bytes: &[u8]) -> [u8; AUDIT_VALUE_LEN] {
+    let mut value = [0; AUDIT_VALUE_LEN];
+    let len = bytes.len().min(AUDIT_VALUE_LEN);
+    value[..len].copy_from_slice(&bytes[..len]);
+    value
+}
+
+/// The audit log to record the changes to, if the feed has one.
+///
+/// Fails if the feed has an audit log and it is not the provided one.
+pub fn get_audit_log<'a, 'info>(
+    configuration: &Configuration,
+    audit_log: &'a Option<AccountLoader<'info, AuditLog>>,
+) -> Result<Option<&'a AccountLoader<'info, AuditLog>>> {
+    if configuration.audit_log == Pubkey::default() {
+        return Ok(None);
+    }
+    match audit_log {
+        Some(audit_log) if audit_log.key() == configuration.audit_log => Ok(Some(audit_log)),
+        _ => {
+            msg!("Expected audit log {}", configuration.audit_log);
+            err!(ScopeError::MissingAuditLog)
+        }
+    }
+}
+
+/// The audit log of the feed, if it has one, expected as the last of the `remaining_accounts`,
+/// and the remaining accounts before it.
+///
+/// Used by the instructions taking remaining accounts, so that the accounts of the feeds without
+/// audit log are unchanged.
+pub fn split_audit_log<'a, 'info>(
+    configuration: &Configuration,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(
+    &'a [AccountInfo<'info>],
+    Option<AccountLoader<'info, AuditLog>>,
+)> {
+    if configuration.audit_log == Pubkey::default() {
+        return Ok((remaining_accounts, None));
+    }
+    match remaining_accounts.split_last() {
+        Some((audit_log, remaining_accounts)) if audit_log.key() == configuration.audit_log => {
+            Ok((
+                remaining_accounts,
+                Some(AccountLoader::try_from(audit_log)?),
+            ))
+        }
+        _ => {
+            msg!(
+                "Expected audit log {} as last remaining account",
+                configuration.audit_log
+            );
+            err!(ScopeError::MissingAuditLog)
+        }
+    }
+}
+
+/// Record a change in the audit log of the feed, if it has one.
+///
+/// Fails if the feed has an audit log and it is not the provided one.
+pub fn record_change(
+    configuration: &Configuration,
+    audit_log: &Option<AccountLoader<AuditLog>>,
+    signer: Pubkey,
+    entry_id: u16,
+    kind: AuditRecordKind,
+    old_value: &[u8],
+    new_value: &[u8],
+) -> Result<()> {
+    if let Some(audit_log) = get_audit_log(configuration, audit_log)? {
+        audit_log.load_mut()?.push(
+            Clock::get()?.slot,
+            signer,
+            entry_id,
+            kind,
+            old_value,
+            new_value,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+
+    use super::*;
+
+    #[test]
+    fn test_records_wrap_around() {
+        let mut audit_log: Box<AuditLog> = Box::new(bytemuck::Zeroable::zeroed());
+        assert_eq!(audit_log.iter_records().count(), 0);
+
+        let signer = Pubkey::new_unique();
+        for slot in 0..AUDIT_LOG_LEN as u64 + 2 {
+            audit_log.push(
+                slot,
+                signer,
+                AUDIT_NO_ENTRY,
+                AuditRecordKind::SetMappingUpdateDelay,
+                &slot.to_le_bytes(),
+                &(slot + 1).to_le_bytes(),
+            );
+        }
+
+        let slots: Vec<u64> = audit_log.iter_records().map(|record| record.slot).collect();
+        assert_eq!(slots.len(), AUDIT_LOG_LEN);
+        assert_eq!(slots[0], 2);
+        assert_eq!(*slots.last().unwrap(), AUDIT_LOG_LEN as u64 + 1);
+
+        let last = audit_log.iter_records().last().unwrap();
+        assert_eq!(last.kind, u8::from(AuditRecordKind::SetMappingUpdateDelay));
+        assert_eq!(
+            last.new_value[..8],
+            (AUDIT_LOG_LEN as u64 + 2).to_le_bytes()
+        );
+        assert_eq!(last.new_value[8..], [0; AUDIT_VALUE_LEN - 8]);
+    }
+
+    #[test]
+    fn test_values_are_truncated() {
+        assert_eq!(
+            to_audit_value(&[1; AUDIT_VALUE_LEN + 1]),
+            [1; AUDIT_VALUE_LEN]
+        );
+    }
+
+    #[test]
+    fn test_split_audit_log() {
+        let audit_log_pk = Pubkey::new_unique();
+        let other_pk = Pubkey::new_unique();
+        let program_id = crate::ID;
+        let (mut audit_log_lamports, mut other_lamports) = (0, 0);
+        let mut audit_log_data = vec![0u8; 8 + AUDIT_LOG_SIZE];
+        audit_log_data[..8].copy_from_slice(&AuditLog::discriminator());
+        let mut other_data: Vec<u8> = vec![];
+        let audit_log = AccountInfo::new(
+            &audit_log_pk,
+            false,
+            true,
+            &mut audit_log_lamports,
+            &mut audit_log_data,
+            &program_id,
+            false,
+            0,
+        );
+        let other = AccountInfo::new(
+            &other_pk,
+            false,
+            false,
+            &mut other_lamports,
+            &mut other_data,
+            &program_id,
+            false,
+            0,
+        );
+
+        let mut configuration: Box<Configuration> = Box::new(bytemuck::Zeroable::zeroed());
+        let accounts = [other.clone(), audit_log.clone()];
+        let (remaining, found) = split_audit_log(&configuration, &accounts).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(found.is_none());
+
+        configuration.audit_log = audit_log_pk;
+        let (remaining, found) = split_audit_log(&configuration, &accounts).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].key(), other_pk);
+        assert_eq!(found.unwrap().key(), audit_log_pk);
+
+        assert!(split_audit_log(&configuration, &[audit_log, other]).is_err());
+        assert!(split_audit_log(&configuration, &[]).is_err());
+    }
+}
@@ -1,6 +1,14 @@
 use anchor_lang::prelude::*;
 
-use crate::utils::consts::CONFIGURATION_SIZE;
+use crate::{utils::consts::CONFIGURATION_SIZE, ScopeError, ScopeResult, MAX_ENTRIES};
+
+/// Maximum number of programs in each of the refresh allowlists
+pub const REFRESH_ALLOWLIST_LEN: usize = 8;
+
+const PAUSED_ENTRIES_BITSET_LEN: usize = MAX_ENTRIES / 64;
+
+/// Maximum number of extension pages of a feed, each page holding `MAX_ENTRIES` more entries
+pub const MAX_EXTENSION_PAGES: usize = 3;
 
 static_assertions::const_assert_eq!(CONFIGURATION_SIZE, std::mem::size_of::<Configuration>());
 static_assertions::const_assert_eq!(0, std::mem::size_of::<Configuration>() % 8);
@@ -13,5 +21,366 @@ pub struct Configuration {
     pub tokens_metadata: Pubkey,
     pub oracle_twaps: Pubkey,
     pub admin_cached: Pubkey,
-    _padding: [u64; 1255],
+    /// Programs allowed to refresh prices through CPI (unset entries are `Pubkey::default()`)
+    pub refresh_cpi_allowlist: [Pubkey; REFRESH_ALLOWLIST_LEN],
+    /// Programs whose top-level instructions may precede a refresh, on top of the compute budget
+    /// program (unset entries are `Pubkey::default()`)
+    pub refresh_preceding_ix_allowlist: [Pubkey; REFRESH_ALLOWLIST_LEN],
+    /// Minimum delay (in seconds) between the proposal and the execution of a mapping update.
+    /// 0 allows immediate updates through `update_mapping_and_metadata`.
+    pub mapping_update_delay_s: u64,
+    /// Shorter delay taking effect at `next_mapping_update_delay_ts` (0 if none is scheduled)
+    pub next_mapping_update_delay_s: u64,
+    pub next_mapping_update_delay_ts: u64,
+    /// Accounts granted a [`Role`] on top of the admin (unset roles are `Pubkey::default()`)
+    pub mapping_admin: Pubkey,
+    pub metadata_admin: Pubkey,
+    pub twap_operator: Pubkey,
+    pub chainlink_resume_operator: Pubkey,
+    pub pauser: Pubkey,
+    /// Non-zero when the refreshes of all the entries are paused
+    pub feed_paused: u8,
+    _padding1: [u8; 7],
+    /// Bitset of the paused group ids, the entries of a paused group are not refreshed
+    pub paused_groups_bitset: u64,
+    /// Bitset of the paused entries
+    pub paused_entries_bitset: [u64; PAUSED_ENTRIES_BITSET_LEN],
+    /// Configurations of the feeds holding the entries of the pages `1..=MAX_EXTENSION_PAGES`
+    /// (unset pages are `Pubkey::default()`). Each page is an independent feed, its entries cannot
+    /// reference the entries of another page.
+    pub extension_pages: [Pubkey; MAX_EXTENSION_PAGES],
+    /// `OraclePrices` of the extension pages, so that consumers can read a page without
+    /// loading its configuration
+    pub extension_oracle_prices: [Pubkey; MAX_EXTENSION_PAGES],
+    /// Feed extended by this one when it is an extension page, `Pubkey::default()` otherwise
+    pub base_configuration: Pubkey,
+    /// Page of this feed in its base feed (0 for a base feed)
+    pub page_index: u8,
+    _padding2: [u8; 7],
+    /// `AuditLog` recording the configuration changes, `Pubkey::default()` if none
+    pub audit_log: Pubkey,
+    _padding: [u64; 1125],
+}
+
+/// What a pause applies to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseTarget {
+    /// All the entries of the feed
+    Feed,
+    /// All the entries tagged with the group id in their token metadata
+    Group(u8),
+    /// A single entry
+    Entry(u16),
+}
+
+/// Permissions that the admin can delegate to another account.
+/// The admin always holds all the roles.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Update the mapping part of the entries (`Mapping*` and `RemoveEntry` updates)
+    MappingAdmin,
+    /// Update the metadata part of the entries (`Metadata*` updates)
+    MetadataAdmin,
+    /// Reset TWAPs
+    TwapOperator,
+    /// Resume suspended ChainlinkX prices
+    ChainlinkResumeOperator,
+    /// Pause and unpause price refreshes
+    Pauser,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefreshAllowlist {
+    /// Programs allowed to CPI into the refresh instructions
+    Cpi,
+    /// Programs allowed to have instructions preceding the refresh instructions
+    PrecedingIx,
+}
+
+impl Configuration {
+    pub fn is_refresh_cpi_allowed(&self, program_id: &Pubkey) -> bool {
+        *program_id != Pubkey::default() && self.refresh_cpi_allowlist.contains(program_id)
+    }
+
+    pub fn is_refresh_preceding_ix_allowed(&self, program_id: &Pubkey) -> bool {
+        *program_id != Pubkey::default() && self.refresh_preceding_ix_allowlist.contains(program_id)
+    }
+
+    /// Mapping update delay in effect at `now`
+    pub fn get_mapping_update_delay_s(&self, now: u64) -> u64 {
+        if self.next_mapping_update_delay_ts != 0 && now >= self.next_mapping_update_delay_ts {
+            self.next_mapping_update_delay_s
+        } else {
+            self.mapping_update_delay_s
+        }
+    }
+
+    /// Set the mapping update delay: increases apply immediately, decreases only once the
+    /// delay in effect has elapsed, so that integrators are always notified in time.
+    pub fn set_mapping_update_delay_s(&mut self, now: u64, delay_s: u64) {
+        let current_delay_s = self.get_mapping_update_delay_s(now);
+        if delay_s >= current_delay_s {
+            self.mapping_update_delay_s = delay_s;
+            self.next_mapping_update_delay_s = 0;
+            self.next_mapping_update_delay_ts = 0;
+        } else {
+            self.mapping_update_delay_s = current_delay_s;
+            self.next_mapping_update_delay_s = delay_s;
+            self.next_mapping_update_delay_ts = now.saturating_add(current_delay_s);
+        }
+    }
+
+    pub fn get_role(&self, role: Role) -> &Pubkey {
+        match role {
+            Role::MappingAdmin => &self.mapping_admin,
+            Role::MetadataAdmin => &self.metadata_admin,
+            Role::TwapOperator => &self.twap_operator,
+            Role::ChainlinkResumeOperator => &self.chainlink_resume_operator,
+            Role::Pauser => &self.pauser,
+        }
+    }
+
+    pub fn get_role_mut(&mut self, role: Role) -> &mut Pubkey {
+        match role {
+            Role::MappingAdmin => &mut self.mapping_admin,
+            Role::MetadataAdmin => &mut self.metadata_admin,
+            Role::TwapOperator => &mut self.twap_operator,
+            Role::ChainlinkResumeOperator => &mut self.chainlink_resume_operator,
+            Role::Pauser => &mut self.pauser,
+        }
+    }
+
+    /// Whether `signer` is the admin or the holder of `role`
+    pub fn has_role(&self, role: Role, signer: &Pubkey) -> bool {
+        *signer == self.admin || (*signer != Pubkey::default() && self.get_role(role) == signer)
+    }
+
+    pub fn is_entry_paused(&self, entry_id: usize, group_ids_bitset: u64) -> bool {
+        self.is_entry_individually_paused(entry_id)
+            || self.paused_groups_bitset & group_ids_bitset != 0
+    }
+
+    /// Whether the entry itself is paused, regardless of its groups
+    pub fn is_entry_individually_paused(&self, entry_id: usize) -> bool {
+        self.paused_entries_bitset[entry_id / 64] & (1 << (entry_id % 64)) != 0
+    }
+
+    /// Fail if the refresh of the entry is paused, by itself, by one of its groups or with the feed
+    pub fn check_refresh_not_paused(
+        &self,
+        entry_id: usize,
+        group_ids_bitset: u64,
+    ) -> ScopeResult<()> {
+        if self.feed_paused != 0 {
+            return Err(ScopeError::FeedPaused);
+        }
+        if self.is_entry_paused(entry_id, group_ids_bitset) {
+            return Err(ScopeError::EntryPaused);
+        }
+        Ok(())
+    }
+
+    /// Whether the target itself is paused, false for an out of range target
+    pub fn is_paused(&self, target: PauseTarget) -> bool {
+        match target {
+            PauseTarget::Feed => self.feed_paused != 0,
+            PauseTarget::Group(group_id) => {
+                u32::from(group_id) < u64::BITS && self.paused_groups_bitset & (1 << group_id) != 0
+            }
+            PauseTarget::Entry(entry_id) => {
+                usize::from(entry_id) < MAX_ENTRIES
+                    && self.is_entry_individually_paused(entry_id.into())
+            }
+        }
+    }
+
+    pub fn set_paused(&mut self, target: PauseTarget, paused: bool) -> ScopeResult<()> {
+        let (bitset, mask) = match target {
+            PauseTarget::Feed => {
+                self.feed_paused = paused.into();
+                return Ok(());
+            }
+            PauseTarget::Group(group_id) => {
+                if u32::from(group_id) >= u64::BITS {
+                    return Err(ScopeError::BadGroupId);
+                }
+                (&mut self.paused_groups_bitset, 1 << group_id)
+            }
+            PauseTarget::Entry(entry_id) => {
+                let entry_id = usize::from(entry_id);
+                if entry_id >= MAX_ENTRIES {
+                    return Err(ScopeError::BadTokenNb);
+                }
+                (
+                    &mut self.paused_entries_bitset[entry_id / 64],
+                    1 << (entry_id % 64),
+                )
+            }
+        };
+        if paused {
+            *bitset |= mask;
+        } else {
+            *bitset &= !mask;
+        }
+        Ok(())
+    }
+
+    pub fn is_extension_page(&self) -> bool {
+        self.base_configuration != Pubkey::default()
+    }
+
+    pub fn has_extension_pages(&self) -> bool {
+        self.extension_pages
+            .iter()
+            .any(|page| *page != Pubkey::default())
+    }
+
+    /// Fail if the feed cannot be closed: it must be paused, so that a live feed is not closed by
+    /// mistake, and unlinked from its pages, so that no link to a closed page is left dangling
+    pub fn check_can_close(&self) -> ScopeResult<()> {
+        if self.feed_paused == 0 {
+            return Err(ScopeError::FeedNotPaused);
+        }
+        if self.has_extension_pages() || self.is_extension_page() {
+            return Err(ScopeError::FeedHasExtensionPages);
+        }
+        Ok(())
+    }
+
+    pub fn get_refresh_allowlist_mut(
+        &mut self,
+        allowlist: RefreshAllowlist,
+    ) -> &mut [Pubkey; REFRESH_ALLOWLIST_LEN] {
+        match allowlist {
+            RefreshAllowlist::Cpi => &mut self.refresh_cpi_allowlist,
+            RefreshAllowlist::PrecedingIx => &mut self.refresh_preceding_ix_allowlist,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_configuration() -> Box<Configuration> {
+        Box::new(bytemuck::Zeroable::zeroed())
+    }
+
+    #[test]
+    fn test_pause_targets() {
+        let mut configuration = new_configuration();
+        configuration
+            .set_paused(PauseTarget::Entry(70), true)
+            .unwrap();
+        configuration
+            .set_paused(PauseTarget::Group(3), true)
+            .unwrap();
+
+        assert!(configuration.is_paused(PauseTarget::Entry(70)));
+        assert!(!configuration.is_paused(PauseTarget::Entry(71)));
+        assert!(configuration.is_paused(PauseTarget::Group(3)));
+        assert!(!configuration.is_paused(PauseTarget::Feed));
+
+        assert_eq!(
+            configuration.check_refresh_not_paused(70, 0),
+            Err(ScopeError::EntryPaused)
+        );
+        assert_eq!(
+            configuration.check_refresh_not_paused(1, 1 << 3),
+            Err(ScopeError::EntryPaused)
+        );
+        assert_eq!(configuration.check_refresh_not_paused(1, 1 << 4), Ok(()));
+
+        configuration.set_paused(PauseTarget::Feed, true).unwrap();
+        assert_eq!(
+            configuration.check_refresh_not_paused(1, 0),
+            Err(ScopeError::FeedPaused)
+        );
+
+        configuration
+            .set_paused(PauseTarget::Entry(70), false)
+            .unwrap();
+        assert!(!configuration.is_paused(PauseTarget::Entry(70)));
+
+        assert_eq!(
+            configuration.set_paused(PauseTarget::Entry(MAX_ENTRIES as u16), true),
+            Err(ScopeError::BadTokenNb)
+        );
+        assert_eq!(
+            configuration.set_paused(PauseTarget::Group(64), true),
+            Err(ScopeError::BadGroupId)
+        );
+        assert!(!configuration.is_paused(PauseTarget::Entry(MAX_ENTRIES as u16)));
+        assert!(!configuration.is_paused(PauseTarget::Group(64)));
+    }
+
+    #[test]
+    fn test_roles() {
+        let mut configuration = new_configuration();
+        let admin = Pubkey::new_unique();
+        let pauser = Pubkey::new_unique();
+        configuration.admin = admin;
+        *configuration.get_role_mut(Role::Pauser) = pauser;
+
+        assert!(configuration.has_role(Role::Pauser, &pauser));
+        assert!(configuration.has_role(Role::Pauser, &admin));
+        assert!(configuration.has_role(Role::MappingAdmin, &admin));
+        assert!(!configuration.has_role(Role::MappingAdmin, &pauser));
+        // Unset roles are not held by the default key
+        assert!(!configuration.has_role(Role::MetadataAdmin, &Pubkey::default()));
+    }
+
+    #[test]
+    fn test_mapping_update_delay_decreases_are_delayed() {
+        let mut configuration = new_configuration();
+        configuration.set_mapping_update_delay_s(1_000, 3_600);
+        assert_eq!(configuration.get_mapping_update_delay_s(1_000), 3_600);
+
+        configuration.set_mapping_update_delay_s(2_000, 60);
+        assert_eq!(configuration.get_mapping_update_delay_s(2_000), 3_600);
+        assert_eq!(configuration.get_mapping_update_delay_s(5_599), 3_600);
+        assert_eq!(configuration.get_mapping_update_delay_s(5_600), 60);
+
+        // Increases apply immediately and cancel the scheduled decrease
+        configuration.set_mapping_update_delay_s(3_000, 7_200);
+        assert_eq!(configuration.get_mapping_update_delay_s(3_000), 7_200);
+        assert_eq!(configuration.get_mapping_update_delay_s(10_000), 7_200);
+    }
+
+    #[test]
+    fn test_refresh_allowlists() {
+        let mut configuration = new_configuration();
+        let program_id = Pubkey::new_unique();
+        configuration.get_refresh_allowlist_mut(RefreshAllowlist::Cpi)[2] = program_id;
+
+        assert!(configuration.is_refresh_cpi_allowed(&program_id));
+        assert!(!configuration.is_refresh_preceding_ix_allowed(&program_id));
+        // Unset slots do not allow the default key
+        assert!(!configuration.is_refresh_cpi_allowed(&Pubkey::default()));
+    }
+
+    #[test]
+    fn test_check_can_close() {
+        let mut configuration = new_configuration();
+        assert_eq!(
+            configuration.check_can_close(),
+            Err(ScopeError::FeedNotPaused)
+        );
+
+        configuration.set_paused(PauseTarget::Feed, true).unwrap();
+        assert_eq!(configuration.check_can_close(), Ok(()));
+
+        configuration.extension_pages[1] = Pubkey::new_unique();
+        assert_eq!(
+            configuration.check_can_close(),
+            Err(ScopeError::FeedHasExtensionPages)
+        );
+
+        configuration.extension_pages[1] = Pubkey::default();
+        configuration.base_configuration = Pubkey::new_unique();
+        assert_eq!(
+            configuration.check_can_close(),
+            Err(ScopeError::FeedHasExtensionPages)
+        );
+    }
 }
@@ -1,16 +1,20 @@
 use anchor_lang::prelude::*;
 
+pub mod audit_log;
 pub mod configuration;
 pub mod mints_to_scope_chains;
 pub mod oracle_mappings;
 pub mod oracle_prices;
 pub mod oracle_twaps;
+pub mod pending_mapping_update;
 pub mod token_metadatas;
-pub use configuration::Configuration;
+pub use audit_log::{AuditLog, AuditRecord, AuditRecordKind, AUDIT_NO_ENTRY};
+pub use configuration::{Configuration, PauseTarget, RefreshAllowlist, Role, MAX_EXTENSION_PAGES};
 pub use oracle_mappings::OracleMappings;
 pub use oracle_prices::OraclePrices;
 pub use oracle_twaps::{EmaTwap, EmaType, OracleTwaps, TwapEnabledBitmask};
-pub use token_metadatas::{TokenMetadata, TokenMetadatas};
+pub use pending_mapping_update::PendingMappingUpdate;
+pub use token_metadatas::{StaleRefPricePolicy, TokenMetadata, TokenMetadatas};
 
 #[zero_copy]
 #[derive(Debug, Default, AnchorDeserialize, AnchorSerialize)]
@@ -37,3 +41,57 @@ pub struct DatedPrice {
     pub unix_timestamp: u64,
     pub generic_data: [u8; 24],
 }
+
+/// Bytes of [`DatedPrice::generic_data`] holding the confidence interval (or standard deviation)
+/// of the price, as a little-endian `u64` expressed with the exponent of the price.
+///
+/// Only set by the oracle types providing one: `PythPull`, `PythPullEMA`, `PythLazer` and
+/// `SwitchboardOnDemand`. Other oracle types may use these bytes for other purposes.
+pub const DATED_PRICE_CONFIDENCE_RANGE: std::ops::Range<usize> = 8..16;
+
+impl DatedPrice {
+    pub fn set_confidence(&mut self, confidence: u64) {
+        self.generic_data[DATED_PRICE_CONFIDENCE_RANGE].copy_from_slice(&confidence.to_le_bytes());
+    }
+
+    /// Confidence interval of the price, see [`DATED_PRICE_CONFIDENCE_RANGE`]
+    pub fn confidence(&self) -> Price {
+        let value = u64::from_le_bytes(
+            self.generic_data[DATED_PRICE_CONFIDENCE_RANGE]
+                .try_into()
+                .unwrap(),
+        );
+        Price {
+            value,
+            exp: self.price.exp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confidence() {
+        let mut dated_price = DatedPrice {
+            price: Price {
+                value: 12345,
+                exp: 6,
+            },
+            ..Default::default()
+        };
+        dated_price.generic_data[..8].copy_from_slice(&[1; 8]);
+        dated_price.set_confidence(25);
+
+        let confidence = dated_price.confidence();
+        assert_eq!((confidence.value, confidence.exp), (25, 6));
+        assert_eq!(
+            dated_price.generic_data[DATED_PRICE_CONFIDENCE_RANGE],
+            25_u64.to_le_bytes()
+        );
+        // The other bytes of the generic data are left untouched
+        assert_eq!(dated_price.generic_data[..8], [1; 8]);
+        assert_eq!(dated_price.generic_data[16..], [0; 8]);
+    }
+}
@@ -0,0 +1,92 @@
+use std::mem::size_of;
+
+use anchor_lang::prelude::*;
+
+use crate::handlers::handler_update_mapping_and_metadata::{
+    UpdateOracleMappingAndMetadataEntriesWithId, UpdateOracleMappingAndMetadataEntry,
+};
+
+/// Mapping and metadata updates proposed for a price feed, applicable once
+/// `earliest_execution_ts` is reached
+#[account]
+pub struct PendingMappingUpdate {
+    pub configuration: Pubkey,
+    /// Payer of the account rent, refunded when the proposal is executed or cancelled
+    pub proposer: Pubkey,
+    pub proposal_id: u64,
+    pub earliest_execution_ts: u64,
+    /// Price accounts expected as remaining accounts at execution, one per `MappingConfig` update
+    pub price_info_accounts: Vec<Pubkey>,
+    pub updates: Vec<UpdateOracleMappingAndMetadataEntriesWithId>,
+}
+
+impl PendingMappingUpdate {
+    pub fn size(updates: &[UpdateOracleMappingAndMetadataEntriesWithId]) -> Result<usize> {
+        let updates_size = updates
+            .try_to_vec()
+            .map_err(|_| error!(ErrorCode::AccountDidNotSerialize))?
+            .len();
+        Ok(size_of::<Pubkey>() // configuration
+            + size_of::<Pubkey>() // proposer
+            + size_of::<u64>() // proposal_id
+            + size_of::<u64>() // earliest_execution_ts
+            + size_of::<u32>() // Vec length
+            + Self::price_info_accounts_count(updates) * size_of::<Pubkey>() // Vec data
+            + updates_size) // updates
+    }
+
+    /// Number of price accounts consumed by the updates
+    pub fn price_info_accounts_count(
+        updates: &[UpdateOracleMappingAndMetadataEntriesWithId],
+    ) -> usize {
+        updates
+            .iter()
+            .flat_map(|entry| entry.updates.iter())
+            .filter(|update| {
+                matches!(
+                    update,
+                    UpdateOracleMappingAndMetadataEntry::MappingConfig { .. }
+                )
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracles::OracleType;
+
+    #[test]
+    fn test_size_is_the_serialized_size() {
+        let updates = vec![
+            UpdateOracleMappingAndMetadataEntriesWithId::new(
+                0,
+                vec![
+                    UpdateOracleMappingAndMetadataEntry::MappingConfig {
+                        price_type: OracleType::PythPull,
+                        generic_data: [0; 20],
+                    },
+                    UpdateOracleMappingAndMetadataEntry::MetadataName("SOL".to_string()),
+                ],
+            ),
+            UpdateOracleMappingAndMetadataEntriesWithId::new(
+                1,
+                vec![UpdateOracleMappingAndMetadataEntry::RemoveEntry],
+            ),
+        ];
+        let pending = PendingMappingUpdate {
+            configuration: Pubkey::new_unique(),
+            proposer: Pubkey::new_unique(),
+            proposal_id: 1,
+            earliest_execution_ts: 2,
+            price_info_accounts: vec![Pubkey::new_unique()],
+            updates: updates.clone(),
+        };
+        assert_eq!(PendingMappingUpdate::price_info_accounts_count(&updates), 1);
+        assert_eq!(
+            PendingMappingUpdate::size(&updates).unwrap(),
+            pending.try_to_vec().unwrap().len()
+        );
+    }
+}
@@ -1,10 +1,35 @@
 use anchor_lang::prelude::*;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    utils::{consts::*, list_set_bit_positions},
+    utils::{consts::*, list_set_bit_positions, math::confidence_bps_to_factor},
     MAX_ENTRIES,
 };
 
+/// What to do with a reference price older than the configured maximum age
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    IntoPrimitive,
+    TryFromPrimitive,
+)]
+#[repr(u8)]
+pub enum StaleRefPricePolicy {
+    /// Reject the new price
+    Reject = 0,
+    /// Ignore the stale reference. The new price must still agree with the configured quorum of
+    /// fresh references (all of them if the quorum is 0), and is rejected if none is left.
+    Skip = 1,
+}
+
+/// Maximum number of reference prices of an entry on top of the mapping `ref_price`
+pub const MAX_ADDITIONAL_REF_PRICES: usize = 3;
+
 static_assertions::const_assert_eq!(TOKEN_METADATA_SIZE, std::mem::size_of::<TokenMetadatas>());
 static_assertions::const_assert_eq!(0, std::mem::size_of::<TokenMetadatas>() % 8);
 #[account(zero_copy)]
@@ -18,7 +43,36 @@ pub struct TokenMetadata {
     pub name: [u8; 32],
     pub max_age_price_slots: u64,
     pub group_ids_bitset: u64, // a bitset of group IDs in range [0, 64).
-    pub _reserved: [u64; 15],
+    /// Maximum move (in bps) allowed between the stored price and a new one per `max_price_move_period_s`.
+    /// 0 disables the check.
+    pub max_price_move_bps: u16,
+    /// Maximum confidence interval (in bps of the price) accepted from oracles providing one.
+    /// 0 uses the default [`ORACLE_CONFIDENCE_FACTOR`].
+    pub confidence_bps: u16,
+    pub max_price_move_period_s: u32,
+    /// Maximum age (in seconds) of the price returned by the oracle at refresh time.
+    /// 0 disables the check.
+    pub max_source_age_s: u64,
+    /// Reference prices checked on top of the mapping `ref_price`, only the first
+    /// `additional_ref_prices_len` are set
+    pub additional_ref_prices: [u16; MAX_ADDITIONAL_REF_PRICES],
+    pub additional_ref_prices_len: u8,
+    /// Number of reference prices a new price must agree with. 0 requires all of them.
+    pub ref_price_quorum: u8,
+    /// Maximum age (in seconds) of a reference price for it to be used. 0 disables the check.
+    pub ref_price_max_age_s: u32,
+    /// [`StaleRefPricePolicy`] applied to reference prices older than `ref_price_max_age_s`
+    pub stale_ref_price_policy: u8,
+    /// Non-zero to recompute this derived entry in `refresh_price_list` and
+    /// `refresh_price_group` when one of its sources is updated by the same instruction.
+    /// Only used by the types computed from other entries without accounts.
+    pub rederive_on_source_refresh: u8,
+    /// Maximum move (in bps) allowed by `max_price_move_bps` however long since the stored price.
+    /// At least [`MIN_PRICE_MOVE_CAP_BPS`] when the check is enabled.
+    ///
+    /// [`MIN_PRICE_MOVE_CAP_BPS`]: crate::utils::consts::MIN_PRICE_MOVE_CAP_BPS
+    pub max_price_move_cap_bps: u16,
+    pub _reserved: [u64; 11],
 }
 
 impl TokenMetadata {
@@ -35,6 +89,23 @@ impl TokenMetadata {
         self.name = padded_name;
     }
 
+    /// Confidence factor to use when validating the confidence interval of a price
+    pub fn oracle_confidence_factor(&self) -> u32 {
+        if self.confidence_bps == 0 {
+            ORACLE_CONFIDENCE_FACTOR
+        } else {
+            confidence_bps_to_factor(self.confidence_bps.into())
+        }
+    }
+
+    pub fn get_additional_ref_prices(&self) -> &[u16] {
+        &self.additional_ref_prices[..usize::from(self.additional_ref_prices_len)]
+    }
+
+    pub fn rederives_on_source_refresh(&self) -> bool {
+        self.rederive_on_source_refresh != 0
+    }
+
     pub fn reset(&mut self) {
         *self = TokenMetadata::default();
     }
@@ -49,6 +120,42 @@ impl std::fmt::Debug for TokenMetadata {
                 "group_ids_bitset",
                 &list_set_bit_positions(self.group_ids_bitset),
             )
+            .field("max_price_move_bps", &self.max_price_move_bps)
+            .field("max_price_move_period_s", &self.max_price_move_period_s)
+            .field("max_price_move_cap_bps", &self.max_price_move_cap_bps)
+            .field("confidence_bps", &self.confidence_bps)
+            .field("max_source_age_s", &self.max_source_age_s)
+            .field("additional_ref_prices", &self.get_additional_ref_prices())
+            .field("ref_price_quorum", &self.ref_price_quorum)
+            .field("ref_price_max_age_s", &self.ref_price_max_age_s)
+            .field(
+                "stale_ref_price_policy",
+                &StaleRefPricePolicy::try_from(self.stale_ref_price_policy),
+            )
+            .field(
+                "rederive_on_source_refresh",
+                &self.rederives_on_source_refresh(),
+            )
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oracle_confidence_factor() {
+        let mut metadata = TokenMetadata::default();
+        assert_eq!(
+            metadata.oracle_confidence_factor(),
+            ORACLE_CONFIDENCE_FACTOR
+        );
+
+        // A price must be more than 100 times its confidence interval for a 1% confidence
+        metadata.confidence_bps = 100;
+        assert_eq!(metadata.oracle_confidence_factor(), 100);
+        metadata.confidence_bps = FULL_BPS;
+        assert_eq!(metadata.oracle_confidence_factor(), 1);
+    }
+}
@@ -3,6 +3,7 @@ pub const ORACLE_MAPPING_SIZE: usize = 29696;
 pub const ORACLE_PRICES_SIZE: usize = 28704;
 pub const ORACLE_TWAPS_SIZE: usize = 344128;
 pub const TOKEN_METADATA_SIZE: usize = 86016;
+pub const AUDIT_LOG_SIZE: usize = 45096;
 
 /// Factor used to check confidence interval of oracle prices
 /// Used when calling [`crate::utils::math::check_confidence_interval`]
@@ -11,6 +12,10 @@ pub const ORACLE_CONFIDENCE_FACTOR: u32 = super::math::confidence_bps_to_factor(
 
 pub const FULL_BPS: u16 = 10_000;
 
+/// Minimum `max_price_move_cap_bps` of a token metadata, so that the price move check cannot
+/// block an entry on a usual price move
+pub const MIN_PRICE_MOVE_CAP_BPS: u16 = 1_000; // 10%
+
 pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
 pub const MILLIS_PER_SECOND: u64 = 1_000;
 pub const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
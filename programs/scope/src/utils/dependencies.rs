@@ -0,0 +1,476 @@
+//! Dependencies between the entries of a feed
+//!
+//! An entry depends on another one when its price is computed from it (TWAP source, composite
+//! oracle sources) or checked against it (ref prices).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anchor_lang::prelude::msg;
+use scope_types::dependencies::{get_refresh_order_by, RefreshOrderError};
+
+use crate::{
+    oracles::{get_composite_source_entries, OracleType},
+    states::{OracleMappings, TokenMetadata},
+    ScopeError, ScopeResult, MAX_ENTRIES,
+};
+
+/// Entries the price of `entry_id` is computed from
+pub fn get_price_sources(oracle_mappings: &OracleMappings, entry_id: usize) -> Vec<u16> {
+    if !oracle_mappings.is_entry_used(entry_id) {
+        return Vec::new();
+    }
+    let Ok(price_type) = oracle_mappings.get_entry_type(entry_id) else {
+        return Vec::new();
+    };
+    if price_type.is_twap() {
+        return oracle_mappings
+            .get_twap_source(entry_id)
+            .map(|source| vec![u16::try_from(source).unwrap()])
+            .unwrap_or_default();
+    }
+    get_composite_source_entries(price_type, &oracle_mappings.generic[entry_id]).unwrap_or_default()
+}
+
+/// Whether the price of `entry_id` is computed from other entries (TWAP and composite types)
+pub fn is_derived_entry(oracle_mappings: &OracleMappings, entry_id: usize) -> bool {
+    !get_price_sources(oracle_mappings, entry_id).is_empty()
+}
+
+/// Entries the price of `entry_id` is checked against
+pub fn get_ref_price_entries(
+    oracle_mappings: &OracleMappings,
+    metadata: &TokenMetadata,
+    entry_id: usize,
+) -> Vec<u16> {
+    oracle_mappings
+        .get_ref_price(entry_id)
+        .into_iter()
+        .chain(metadata.get_additional_ref_prices().iter().copied())
+        .collect()
+}
+
+/// Type and dependencies of an entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntryDependencies {
+    /// `None` if the entry is unused
+    pub price_type: Option<OracleType>,
+    /// Entries the price is computed from
+    pub price_sources: Vec<u16>,
+    /// Entries the price is checked against
+    pub ref_prices: Vec<u16>,
+}
+
+impl EntryDependencies {
+    /// Price sources and ref prices, sorted and deduplicated
+    pub fn get_dependencies(&self) -> Vec<u16> {
+        let mut dependencies: Vec<u16> = self
+            .price_sources
+            .iter()
+            .chain(self.ref_prices.iter())
+            .copied()
+            .collect();
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        dependencies
+    }
+}
+
+/// Entries of a feed, as seen by the dependency checks
+pub trait FeedEntries {
+    fn get_entry_dependencies(&self, entry_id: usize) -> EntryDependencies;
+}
+
+/// Entries of a feed as stored in its accounts
+pub struct Feed<'a> {
+    pub oracle_mappings: &'a OracleMappings,
+    pub metadatas: &'a [TokenMetadata],
+}
+
+impl<'a> Feed<'a> {
+    pub fn new(oracle_mappings: &'a OracleMappings, metadatas: &'a [TokenMetadata]) -> Self {
+        Self {
+            oracle_mappings,
+            metadatas,
+        }
+    }
+}
+
+impl FeedEntries for Feed<'_> {
+    fn get_entry_dependencies(&self, entry_id: usize) -> EntryDependencies {
+        if !self.oracle_mappings.is_entry_used(entry_id) {
+            return EntryDependencies::default();
+        }
+        EntryDependencies {
+            price_type: self.oracle_mappings.get_entry_type(entry_id).ok(),
+            price_sources: get_price_sources(self.oracle_mappings, entry_id),
+            ref_prices: get_ref_price_entries(
+                self.oracle_mappings,
+                &self.metadatas[entry_id],
+                entry_id,
+            ),
+        }
+    }
+}
+
+/// Entries of a feed once a batch of updates is applied, without writing the feed accounts
+pub struct UpdatedFeed<'a> {
+    pub feed: Feed<'a>,
+    /// State of the updated entries after the batch
+    pub updated_entries: BTreeMap<usize, EntryDependencies>,
+}
+
+impl FeedEntries for UpdatedFeed<'_> {
+    fn get_entry_dependencies(&self, entry_id: usize) -> EntryDependencies {
+        match self.updated_entries.get(&entry_id) {
+            Some(entry) => entry.clone(),
+            None => self.feed.get_entry_dependencies(entry_id),
+        }
+    }
+}
+
+/// Used entries depending on one of `entries`, as `(dependent, dependency)` pairs
+///
+/// Each entry of the feed is decoded once whatever the number of `entries`. The entries of the
+/// extension pages are not scanned: a page is a feed of its own and its entries cannot reference
+/// the entries of another page.
+pub fn get_dependents(feed: &impl FeedEntries, entries: &BTreeSet<u16>) -> Vec<(usize, u16)> {
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    (0..MAX_ENTRIES)
+        .flat_map(|dependent| {
+            feed.get_entry_dependencies(dependent)
+                .get_dependencies()
+                .into_iter()
+                .filter(move |dependency| {
+                    usize::from(*dependency) != dependent && entries.contains(dependency)
+                })
+                .map(move |dependency| (dependent, dependency))
+        })
+        .collect()
+}
+
+/// Refuse removing or retyping an entry other entries depend on (as a price source or ref price)
+///
+/// No entry may still depend on a removed entry. The dependents of a retyped entry must be
+/// reviewed in the same batch: they must be retyped too or have their sources or ref prices
+/// changed. `initial_entries` holds the state of every updated entry before the batch.
+pub fn check_dependents(
+    feed: &impl FeedEntries,
+    initial_entries: &BTreeMap<usize, EntryDependencies>,
+) -> ScopeResult {
+    let removed_or_retyped: BTreeSet<u16> = initial_entries
+        .iter()
+        .filter(|&(&entry_id, initial)| {
+            initial.price_type.is_some()
+                && feed.get_entry_dependencies(entry_id).price_type != initial.price_type
+        })
+        .map(|(&entry_id, _)| u16::try_from(entry_id).unwrap())
+        .collect();
+
+    let mut result = Ok(());
+    for (dependent, dependency) in get_dependents(feed, &removed_or_retyped) {
+        if feed
+            .get_entry_dependencies(dependency.into())
+            .price_type
+            .is_none()
+        {
+            msg!("Entry {dependency} is removed but still referenced by entry {dependent}");
+            result = Err(ScopeError::EntryHasDependents);
+            continue;
+        }
+        let dependent_reviewed = initial_entries.get(&dependent).is_some_and(|initial| {
+            let current = feed.get_entry_dependencies(dependent);
+            current.price_type != initial.price_type
+                || current.get_dependencies() != initial.get_dependencies()
+        });
+        if !dependent_reviewed {
+            msg!("Entry {dependency} changes type but its dependent entry {dependent} is not retyped and keeps the same sources and ref prices");
+            result = Err(ScopeError::EntryHasDependents);
+        }
+    }
+    result
+}
+
+/// Fail if the price of `entry_id` is, directly or transitively, computed from itself
+pub fn check_no_dependency_cycle(feed: &impl FeedEntries, entry_id: usize) -> ScopeResult {
+    let mut visited = [false; MAX_ENTRIES];
+    let mut to_visit = feed.get_entry_dependencies(entry_id).price_sources;
+    while let Some(source) = to_visit.pop() {
+        let source = usize::from(source);
+        if source == entry_id {
+            return Err(ScopeError::DependencyCycle);
+        }
+        if source >= MAX_ENTRIES || visited[source] {
+            continue;
+        }
+        visited[source] = true;
+        to_visit.extend(feed.get_entry_dependencies(source).price_sources);
+    }
+    Ok(())
+}
+
+/// Order `tokens` so that every derived entry comes after its sources, adding the derived
+/// entries the requested ones transitively depend on.
+///
+/// Non-derived sources are not added as refreshing them requires their price accounts.
+/// Each entry appears once in the returned list.
+///
+/// Shares its implementation with [`scope_types::dependencies::get_refresh_order`], used by the
+/// clients to order their refreshes.
+pub fn get_refresh_order(
+    oracle_mappings: &OracleMappings,
+    tokens: &[u16],
+) -> ScopeResult<Vec<u16>> {
+    get_refresh_order_by(tokens, |entry_id| {
+        get_price_sources(oracle_mappings, entry_id)
+    })
+    .map_err(|err| match err {
+        RefreshOrderError::EntryOutOfRange(entry) => {
+            msg!("Entry {entry} is out of range");
+            ScopeError::BadTokenNb
+        }
+        RefreshOrderError::DependencyCycle(entry) => {
+            msg!("Entry {entry} depends on itself");
+            ScopeError::DependencyCycle
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+
+    use super::*;
+    use crate::utils::test_utils::{new_feed, set_most_recent_of_entry, set_pyth_entry};
+
+    fn initial_entries(
+        oracle_mappings: &OracleMappings,
+        metadatas: &[TokenMetadata],
+        entries: &[usize],
+    ) -> BTreeMap<usize, EntryDependencies> {
+        let feed = Feed::new(oracle_mappings, metadatas);
+        entries
+            .iter()
+            .map(|&entry_id| (entry_id, feed.get_entry_dependencies(entry_id)))
+            .collect()
+    }
+
+    #[test]
+    fn test_get_dependents_of_sources_and_ref_prices() {
+        let (mut oracle_mappings, mut metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_pyth_entry(&mut oracle_mappings, 1);
+        set_most_recent_of_entry(&mut oracle_mappings, 2, &[0, 1]);
+        set_pyth_entry(&mut oracle_mappings, 3);
+        oracle_mappings.set_ref_price(3, Some(1));
+        set_pyth_entry(&mut oracle_mappings, 4);
+        metadatas[4].additional_ref_prices[0] = 0;
+        metadatas[4].additional_ref_prices_len = 1;
+
+        let feed = Feed::new(&oracle_mappings, &metadatas);
+        assert_eq!(
+            feed.get_entry_dependencies(2),
+            EntryDependencies {
+                price_type: Some(OracleType::MostRecentOf),
+                price_sources: vec![0, 1],
+                ref_prices: vec![],
+            }
+        );
+        assert_eq!(
+            get_dependents(&feed, &BTreeSet::from([0])),
+            vec![(2, 0), (4, 0)]
+        );
+        assert_eq!(
+            get_dependents(&feed, &BTreeSet::from([0, 1])),
+            vec![(2, 0), (2, 1), (3, 1), (4, 0)]
+        );
+        assert!(get_dependents(&feed, &BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_removing_a_referenced_entry_is_refused() {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_most_recent_of_entry(&mut oracle_mappings, 1, &[0]);
+
+        // Even when the dependent is updated in the same batch
+        let initial = initial_entries(&oracle_mappings, &metadatas, &[0, 1]);
+        oracle_mappings.reset_entry(0);
+        assert_eq!(
+            check_dependents(&Feed::new(&oracle_mappings, &metadatas), &initial),
+            Err(ScopeError::EntryHasDependents)
+        );
+
+        // Removing the dependent too is fine
+        oracle_mappings.reset_entry(1);
+        assert_eq!(
+            check_dependents(&Feed::new(&oracle_mappings, &metadatas), &initial),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_retyping_requires_reviewing_the_dependents() {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_pyth_entry(&mut oracle_mappings, 1);
+        set_most_recent_of_entry(&mut oracle_mappings, 2, &[0]);
+        set_pyth_entry(&mut oracle_mappings, 3);
+
+        // The dependent is not part of the batch
+        let mut retyped = oracle_mappings.clone();
+        let initial = initial_entries(&retyped, &metadatas, &[0]);
+        retyped.set_entry_mapping(0, None, OracleType::MostRecentOf, [0xff; 20]);
+        assert_eq!(
+            check_dependents(&Feed::new(&retyped, &metadatas), &initial),
+            Err(ScopeError::EntryHasDependents)
+        );
+
+        // The dependent is part of the batch but its sources did not change
+        let initial = initial_entries(&oracle_mappings, &metadatas, &[0, 2]);
+        let mut retyped = oracle_mappings.clone();
+        retyped.set_entry_mapping(0, None, OracleType::MostRecentOf, [0xff; 20]);
+        retyped.set_ref_price_tolerance_bps(2, Some(100)).unwrap();
+        assert_eq!(
+            check_dependents(&Feed::new(&retyped, &metadatas), &initial),
+            Err(ScopeError::EntryHasDependents)
+        );
+
+        // The dependent sources changed
+        let mut updated = retyped.clone();
+        set_most_recent_of_entry(&mut updated, 2, &[0, 1]);
+        assert_eq!(
+            check_dependents(&Feed::new(&updated, &metadatas), &initial),
+            Ok(())
+        );
+
+        // The dependent ref prices changed
+        let mut updated = retyped.clone();
+        updated.set_ref_price(2, Some(3));
+        assert_eq!(
+            check_dependents(&Feed::new(&updated, &metadatas), &initial),
+            Ok(())
+        );
+
+        // The dependent is retyped too
+        let mut updated = retyped;
+        updated.set_entry_mapping(2, Some(Pubkey::new_unique()), OracleType::PythPull, [0; 20]);
+        updated.set_ref_price(2, Some(0));
+        assert_eq!(
+            check_dependents(&Feed::new(&updated, &metadatas), &initial),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_entries_without_dependents_can_change() {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_most_recent_of_entry(&mut oracle_mappings, 1, &[0]);
+
+        let initial = initial_entries(&oracle_mappings, &metadatas, &[1, 2]);
+        oracle_mappings.reset_entry(1);
+        set_pyth_entry(&mut oracle_mappings, 2);
+        assert_eq!(
+            check_dependents(&Feed::new(&oracle_mappings, &metadatas), &initial),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_dependency_cycles() {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_most_recent_of_entry(&mut oracle_mappings, 1, &[0]);
+        set_most_recent_of_entry(&mut oracle_mappings, 2, &[1, 0]);
+        // Ref prices do not make cycles
+        oracle_mappings.set_ref_price(0, Some(2));
+
+        let feed = Feed::new(&oracle_mappings, &metadatas);
+        for entry_id in 0..3 {
+            assert_eq!(check_no_dependency_cycle(&feed, entry_id), Ok(()));
+        }
+
+        set_most_recent_of_entry(&mut oracle_mappings, 1, &[0, 2]);
+        let feed = Feed::new(&oracle_mappings, &metadatas);
+        assert_eq!(
+            check_no_dependency_cycle(&feed, 1),
+            Err(ScopeError::DependencyCycle)
+        );
+        assert_eq!(
+            check_no_dependency_cycle(&feed, 2),
+            Err(ScopeError::DependencyCycle)
+        );
+    }
+
+    #[test]
+    fn test_updated_feed_overrides_the_updated_entries() {
+        let (mut oracle_mappings, metadatas) = new_feed();
+        set_pyth_entry(&mut oracle_mappings, 0);
+        set_most_recent_of_entry(&mut oracle_mappings, 1, &[0]);
+        let initial = initial_entries(&oracle_mappings, &metadatas, &[0, 1]);
+
+        // Entry 0 now derives from entry 1, which keeps depending on entry 0
+        let updated_feed = UpdatedFeed {
+            feed: Feed::new(&oracle_mappings, &metadatas),
+            updated_entries: BTreeMap::from([(
+                0,
+                EntryDependencies {
+                    price_type: Some(OracleType::MostRecentOf),
+                    price_sources: vec![1],
+                    ref_prices: vec![],
+                },
+            )]),
+        };
+        assert_eq!(updated_feed.get_entry_dependencies(1), initial[&1],);
+        assert_eq!(
+            check_no_dependency_cycle(&updated_feed, 0),
+            Err(ScopeError::DependencyCycle)
+        );
+        assert_eq!(
+            check_dependents(&updated_feed, &initial),
+            Err(ScopeError::EntryHasDependents)
+        );
+
+        // The accounts are untouched
+        assert_eq!(
+            check_no_dependency_cycle(&Feed::new(&oracle_mappings, &metadatas), 0),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_refresh_order_matches_scope_types() {
+        use scope_types::dependencies::{self as types, fixtures::dependencies_fixture};
+
+        for (type_id, price_type) in [
+            (types::SCOPE_TWAP_1H, OracleType::ScopeTwap1h),
+            (types::MOST_RECENT_OF, OracleType::MostRecentOf),
+            (types::SECURITIZE, OracleType::Securitize),
+            (types::CAPPED_FLOORED, OracleType::CappedFloored),
+            (types::CAPPED_MOST_RECENT_OF, OracleType::CappedMostRecentOf),
+            (types::SCOPE_TWAP_8H, OracleType::ScopeTwap8h),
+            (types::SCOPE_TWAP_24H, OracleType::ScopeTwap24h),
+            (types::MEDIAN_OF, OracleType::MedianOf),
+        ] {
+            assert_eq!(type_id, u8::from(price_type));
+        }
+
+        let fixture = dependencies_fixture();
+        let oracle_mappings: &OracleMappings =
+            bytemuck::from_bytes(bytemuck::bytes_of(&*fixture.oracle_mappings));
+        for entry_id in 0..MAX_ENTRIES {
+            assert_eq!(
+                get_price_sources(oracle_mappings, entry_id),
+                types::get_price_sources(&fixture.oracle_mappings, entry_id),
+                "sources of entry {entry_id}"
+            );
+        }
+        assert_eq!(oracle_mappings.get_entry_type(0), Ok(OracleType::PythPull));
+        assert_eq!(oracle_mappings.get_entry_type(2), Ok(OracleType::RedStone));
+        for (tokens, order) in fixture.refresh_orders {
+            assert_eq!(get_refresh_order(oracle_mappings, &tokens), Ok(order));
+        }
+    }
+}
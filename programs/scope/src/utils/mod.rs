@@ -1,15 +1,18 @@
 pub mod consts;
+pub mod dependencies;
 pub mod macros;
 pub mod math;
 pub mod pdas;
 pub mod price_impl;
 pub mod scope_chain;
+#[cfg(test)]
+pub mod test_utils;
 
 use std::cell::{Ref, RefMut};
 
 use anchor_lang::{
     __private::bytemuck,
-    prelude::{AccountDeserialize, AccountInfo},
+    prelude::{AccountDeserialize, AccountInfo, Pubkey},
     Discriminator, Key,
 };
 pub use decimal_wad;
@@ -100,6 +103,20 @@ pub fn zero_copy_deserialize_mut<'info, T: bytemuck::Pod + Discriminator>(
     }))
 }
 
+/// Check that the account is owned by one of the expected programs
+pub fn check_account_owner(account: &AccountInfo, expected_owners: &[Pubkey]) -> ScopeResult<()> {
+    if !expected_owners.contains(account.owner) {
+        warn!(
+            "Account {} is owned by {}, expected one of {:?}",
+            account.key(),
+            account.owner,
+            expected_owners
+        );
+        return Err(ScopeError::WrongAccountOwner);
+    }
+    Ok(())
+}
+
 /// Changes an AccountInfo to an Option<AccountInfo>:
 /// - Some(_) if the account is different from the program
 /// - None if the account is the program
@@ -3,6 +3,7 @@ use anchor_lang::prelude::*;
 pub mod seeds {
     pub const CONFIG: &[u8] = b"conf";
     pub const MINTS_TO_SCOPE_CHAINS: &[u8] = b"mints_to_scope_chains";
+    pub const PENDING_MAPPING_UPDATE: &[u8] = b"pending_mapping_update";
 }
 
 pub fn config_pubkey(price_feed: &str) -> (Pubkey, u8) {
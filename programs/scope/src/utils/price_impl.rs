@@ -5,7 +5,11 @@ use decimal_wad::decimal::Decimal;
 
 use super::math::ten_pow;
 use crate::utils::consts::FULL_BPS;
-use crate::{warn, Price, ScopeError};
+use crate::{
+    events::RefPriceCheckFailed,
+    states::{OracleMappings, OraclePrices, StaleRefPricePolicy, TokenMetadata},
+    warn, DatedPrice, Price, ScopeError, ScopeResult,
+};
 
 pub const MAX_REF_RATIO_TOLERANCE_BPS: u16 = 500;
 pub const MAX_SAFE_EXP_DIFF: u64 = 19;
@@ -57,6 +61,183 @@ pub fn check_ref_price_difference(
     Ok(())
 }
 
+/// Check that the new price of `token` is close enough to its reference prices, if it has any.
+///
+/// The references are the mapping `ref_price` and the additional references of the entry. The
+/// price must agree with at least `ref_price_quorum` of them (all of them if the quorum is 0), or
+/// the check fails with [`ScopeError::RefPriceQuorumNotMet`] ([`ScopeError::PriceNotValid`] when
+/// the entry has no additional references).
+///
+/// References older than the entry `ref_price_max_age_s` are either ignored or make the check
+/// fail with [`ScopeError::RefPriceTooOld`], depending on the entry `stale_ref_price_policy`.
+/// Ignored references still count in an explicit quorum, and the check fails with
+/// [`ScopeError::RefPriceTooOld`] if all the references are stale.
+///
+/// Emits a [`RefPriceCheckFailed`] event for each disagreeing reference when the check fails.
+pub fn check_entry_ref_price(
+    oracle_mappings: &OracleMappings,
+    oracle_prices: &OraclePrices,
+    token_metadata: &TokenMetadata,
+    token: u16,
+    new_price: Price,
+) -> Result<()> {
+    let token_idx = usize::from(token);
+    let ref_prices: Vec<(u16, DatedPrice)> = oracle_mappings
+        .get_ref_price(token_idx)
+        .into_iter()
+        .chain(token_metadata.get_additional_ref_prices().iter().copied())
+        .map(|ref_token| (ref_token, oracle_prices.prices[usize::from(ref_token)]))
+        .collect();
+    if ref_prices.is_empty() {
+        return Ok(());
+    }
+    let now = if token_metadata.ref_price_max_age_s != 0 {
+        u64::try_from(Clock::get()?.unix_timestamp).unwrap()
+    } else {
+        0
+    };
+
+    check_ref_prices(
+        token,
+        new_price,
+        &ref_prices,
+        oracle_mappings.get_ref_price_tolerance_bps(token_idx),
+        token_metadata,
+        now,
+    )
+}
+
+/// Check `new_price` of `token` against the given reference prices, see [`check_entry_ref_price`]
+fn check_ref_prices(
+    token: u16,
+    new_price: Price,
+    ref_prices: &[(u16, DatedPrice)],
+    ref_price_tolerance_bps: Option<u16>,
+    token_metadata: &TokenMetadata,
+    now: u64,
+) -> Result<()> {
+    // A reference set both as the mapping ref price and as an additional one only counts once
+    let ref_prices: Vec<(u16, DatedPrice)> = ref_prices
+        .iter()
+        .enumerate()
+        .filter(|(i, (ref_token, _))| {
+            !ref_prices[..*i]
+                .iter()
+                .any(|(other_token, _)| other_token == ref_token)
+        })
+        .map(|(_, ref_price)| *ref_price)
+        .collect();
+    let all_refs_count = ref_prices.len();
+    // Set aside the stale references, or fail, depending on the entry policy
+    let ref_prices: Vec<(u16, Price)> = if token_metadata.ref_price_max_age_s != 0 {
+        let policy = StaleRefPricePolicy::try_from(token_metadata.stale_ref_price_policy)
+            .map_err(|_| ScopeError::InvalidGenericData)?;
+        let mut fresh_ref_prices = Vec::with_capacity(ref_prices.len());
+        for (ref_token, ref_price) in &ref_prices {
+            let age_s = now.saturating_sub(ref_price.unix_timestamp);
+            if age_s <= u64::from(token_metadata.ref_price_max_age_s) {
+                fresh_ref_prices.push((*ref_token, ref_price.price));
+                continue;
+            }
+            warn!(
+                "Ref price {ref_token} of token {token} is {age_s}s old, max {}s",
+                token_metadata.ref_price_max_age_s
+            );
+            if policy == StaleRefPricePolicy::Reject {
+                return err!(ScopeError::RefPriceTooOld);
+            }
+        }
+        if fresh_ref_prices.is_empty() {
+            warn!("All the reference prices of token {token} are stale");
+            return err!(ScopeError::RefPriceTooOld);
+        }
+        fresh_ref_prices
+    } else {
+        ref_prices
+            .iter()
+            .map(|(ref_token, ref_price)| (*ref_token, ref_price.price))
+            .collect()
+    };
+
+    // Stale references do not lower an explicit quorum
+    let required = match token_metadata.ref_price_quorum {
+        0 => ref_prices.len(),
+        quorum => usize::from(quorum).min(all_refs_count),
+    };
+
+    let mut agreeing = 0;
+    let mut disagreeing = Vec::new();
+    for (ref_token, ref_price) in ref_prices {
+        match check_ref_price_difference(new_price, ref_price, ref_price_tolerance_bps) {
+            Ok(()) => agreeing += 1,
+            Err(_) => disagreeing.push((ref_token, ref_price)),
+        }
+    }
+    if agreeing >= required {
+        return Ok(());
+    }
+
+    for (ref_token, ref_price) in disagreeing {
+        emit!(RefPriceCheckFailed {
+            token,
+            ref_token,
+            price: new_price,
+            ref_price,
+            tolerance_bps: ref_price_tolerance_bps.unwrap_or(MAX_REF_RATIO_TOLERANCE_BPS),
+        });
+    }
+    warn!("Price of token {token} agrees with {agreeing} reference prices, {required} required");
+    if token_metadata.get_additional_ref_prices().is_empty() {
+        err!(ScopeError::PriceNotValid)
+    } else {
+        err!(ScopeError::RefPriceQuorumNotMet)
+    }
+}
+
+/// Check that `new_price` did not move away from `previous_price` by more than `max_move_bps`
+/// for each started `period_s` elapsed between the two prices, up to `max_move_cap_bps`.
+///
+/// A `max_move_cap_bps` below `max_move_bps` caps the allowed move to `max_move_bps` whatever the
+/// elapsed time, so the metadata updates require a cap of at least `MIN_PRICE_MOVE_CAP_BPS`.
+/// The check is skipped if `max_move_bps` is 0 or if there is no previous price.
+pub fn check_price_move(
+    previous_price: &DatedPrice,
+    new_price: &DatedPrice,
+    max_move_bps: u16,
+    period_s: u32,
+    max_move_cap_bps: u16,
+) -> ScopeResult<()> {
+    if max_move_bps == 0 || previous_price.price.value == 0 {
+        return Ok(());
+    }
+
+    let elapsed_s = new_price
+        .unix_timestamp
+        .saturating_sub(previous_price.unix_timestamp);
+    let elapsed_periods = elapsed_s.div_ceil(u64::from(period_s.max(1))).max(1);
+    let allowed_move_bps = u64::from(max_move_bps)
+        .saturating_mul(elapsed_periods)
+        .min(u64::from(max_move_cap_bps.max(max_move_bps)));
+
+    let previous_price_decimal = Decimal::from(previous_price.price);
+    let new_price_decimal = Decimal::from(new_price.price);
+    let absolute_diff = if previous_price_decimal > new_price_decimal {
+        previous_price_decimal - new_price_decimal
+    } else {
+        new_price_decimal - previous_price_decimal
+    };
+
+    if absolute_diff * FULL_BPS > previous_price_decimal * allowed_move_bps {
+        warn!(
+            "Price moved too much: from {} to {} in {}s; max allowed move in bps = {}",
+            previous_price_decimal, new_price_decimal, elapsed_s, allowed_move_bps
+        );
+        return Err(ScopeError::PriceMoveTooLarge);
+    }
+
+    Ok(())
+}
+
 fn decimal_to_price(decimal: Decimal) -> Price {
     // this implementation aims to keep as much precision as possible
     // choose exp to be as big as possible (minimize what is needed for the integer part)
@@ -172,3 +353,167 @@ impl PartialOrd for Price {
         Some(self.cmp(other))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dated_price(value: u64, unix_timestamp: u64) -> DatedPrice {
+        DatedPrice {
+            price: Price { value, exp: 0 },
+            last_updated_slot: 0,
+            unix_timestamp,
+            generic_data: [0; 24],
+        }
+    }
+
+    fn ref_prices(prices: &[(u16, u64, u64)]) -> Vec<(u16, DatedPrice)> {
+        prices
+            .iter()
+            .map(|&(ref_token, value, unix_timestamp)| {
+                (ref_token, dated_price(value, unix_timestamp))
+            })
+            .collect()
+    }
+
+    /// Metadata of an entry whose mapping ref price is 1, with the additional ref prices 2 and 3
+    fn ref_metadata(quorum: u8, max_age_s: u32, policy: StaleRefPricePolicy) -> TokenMetadata {
+        TokenMetadata {
+            additional_ref_prices: [2, 3, 0],
+            additional_ref_prices_len: 2,
+            ref_price_quorum: quorum,
+            ref_price_max_age_s: max_age_s,
+            stale_ref_price_policy: policy.into(),
+            ..Default::default()
+        }
+    }
+
+    fn scope_error(res: Result<()>) -> Option<u32> {
+        res.err().as_ref().and_then(crate::events::error_code)
+    }
+
+    fn error_code(err: ScopeError) -> Option<u32> {
+        Some(err.into())
+    }
+
+    #[test]
+    fn test_ref_prices_quorum() {
+        let price = Price {
+            value: 10_000,
+            exp: 0,
+        };
+        let refs = ref_prices(&[(1, 10_000, 0), (2, 10_100, 0), (3, 12_000, 0)]);
+        let metadata = ref_metadata(2, 0, StaleRefPricePolicy::Reject);
+        assert!(check_ref_prices(0, price, &refs, Some(100), &metadata, 0).is_ok());
+
+        // Quorum 0 requires all the references
+        let metadata = ref_metadata(0, 0, StaleRefPricePolicy::Reject);
+        assert_eq!(
+            scope_error(check_ref_prices(0, price, &refs, Some(100), &metadata, 0)),
+            error_code(ScopeError::RefPriceQuorumNotMet)
+        );
+
+        // Without additional references, the mapping ref price check keeps its original error
+        assert_eq!(
+            scope_error(check_ref_prices(
+                0,
+                price,
+                &refs[2..],
+                Some(100),
+                &TokenMetadata::default(),
+                0
+            )),
+            error_code(ScopeError::PriceNotValid)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_ref_prices_count_once() {
+        let price = Price {
+            value: 10_000,
+            exp: 0,
+        };
+        // Ref price 1 set both in the mapping and in the metadata
+        let refs = ref_prices(&[(1, 10_000, 0), (1, 10_000, 0), (3, 12_000, 0)]);
+        let metadata = ref_metadata(2, 0, StaleRefPricePolicy::Reject);
+        assert_eq!(
+            scope_error(check_ref_prices(0, price, &refs, Some(100), &metadata, 0)),
+            error_code(ScopeError::RefPriceQuorumNotMet)
+        );
+        let metadata = ref_metadata(1, 0, StaleRefPricePolicy::Reject);
+        assert!(check_ref_prices(0, price, &refs, Some(100), &metadata, 0).is_ok());
+    }
+
+    #[test]
+    fn test_stale_ref_prices() {
+        let price = Price {
+            value: 10_000,
+            exp: 0,
+        };
+        let now = 1000;
+        let refs = ref_prices(&[(1, 10_000, now), (2, 10_000, now - 100), (3, 12_000, now)]);
+
+        let reject = ref_metadata(1, 60, StaleRefPricePolicy::Reject);
+        assert_eq!(
+            scope_error(check_ref_prices(0, price, &refs, Some(100), &reject, now)),
+            error_code(ScopeError::RefPriceTooOld)
+        );
+
+        // The stale reference is ignored but the quorum of 2 is kept
+        let skip = ref_metadata(2, 60, StaleRefPricePolicy::Skip);
+        assert_eq!(
+            scope_error(check_ref_prices(0, price, &refs, Some(100), &skip, now)),
+            error_code(ScopeError::RefPriceQuorumNotMet)
+        );
+        let skip = ref_metadata(1, 60, StaleRefPricePolicy::Skip);
+        assert!(check_ref_prices(0, price, &refs, Some(100), &skip, now).is_ok());
+
+        // No fresh reference left
+        assert_eq!(
+            scope_error(check_ref_prices(
+                0,
+                price,
+                &refs[1..2],
+                Some(100),
+                &skip,
+                now
+            )),
+            error_code(ScopeError::RefPriceTooOld)
+        );
+    }
+
+    #[test]
+    fn test_price_move_within_period() {
+        let previous = dated_price(10_000, 1000);
+        // 1% allowed per 60s
+        assert!(check_price_move(&previous, &dated_price(10_100, 1030), 100, 60, 0).is_ok());
+        assert_eq!(
+            check_price_move(&previous, &dated_price(10_101, 1030), 100, 60, 0),
+            Err(ScopeError::PriceMoveTooLarge)
+        );
+        assert!(check_price_move(&previous, &dated_price(9_900, 1030), 100, 60, 0).is_ok());
+    }
+
+    #[test]
+    fn test_price_move_grows_with_elapsed_periods_up_to_cap() {
+        let previous = dated_price(10_000, 1000);
+        // 3 periods elapsed: 3% allowed with a 5% cap
+        assert!(check_price_move(&previous, &dated_price(10_300, 1180), 100, 60, 500).is_ok());
+        assert!(check_price_move(&previous, &dated_price(10_301, 1180), 100, 60, 500).is_err());
+        // A day elapsed: still capped to 5%
+        assert!(check_price_move(&previous, &dated_price(10_500, 87_400), 100, 60, 500).is_ok());
+        assert_eq!(
+            check_price_move(&previous, &dated_price(10_501, 87_400), 100, 60, 500),
+            Err(ScopeError::PriceMoveTooLarge)
+        );
+        // No cap configured: no accumulation
+        assert!(check_price_move(&previous, &dated_price(10_101, 87_400), 100, 60, 0).is_err());
+    }
+
+    #[test]
+    fn test_price_move_disabled() {
+        let previous = dated_price(10_000, 1000);
+        assert!(check_price_move(&previous, &dated_price(20_000, 1000), 0, 0, 0).is_ok());
+        assert!(check_price_move(&dated_price(0, 0), &previous, 100, 60, 0).is_ok());
+    }
+}
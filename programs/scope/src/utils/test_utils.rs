@@ -0,0 +1,104 @@
+//! Fixtures shared by the unit tests
+
+use anchor_lang::{prelude::*, Discriminator, ZeroCopy};
+
+use crate::{
+    oracles::{
+        most_recent_of::{MostRecentOfData, MOST_RECENT_OF_CHAIN_SIZE},
+        OracleType,
+    },
+    states::{OracleMappings, TokenMetadata},
+    MAX_ENTRIES,
+};
+
+/// Mappings and metadatas of a feed without any entry
+pub fn new_feed() -> (Box<OracleMappings>, Vec<TokenMetadata>) {
+    let mut oracle_mappings: Box<OracleMappings> = Box::new(bytemuck::Zeroable::zeroed());
+    for entry_id in 0..MAX_ENTRIES {
+        oracle_mappings.reset_entry(entry_id);
+    }
+    (oracle_mappings, vec![TokenMetadata::default(); MAX_ENTRIES])
+}
+
+pub fn set_pyth_entry(oracle_mappings: &mut OracleMappings, entry_id: usize) {
+    oracle_mappings.reset_entry(entry_id);
+    oracle_mappings.set_entry_mapping(
+        entry_id,
+        Some(Pubkey::new_unique()),
+        OracleType::PythPull,
+        [0; 20],
+    );
+}
+
+/// Generic data of a `MostRecentOf` entry of `sources`
+pub fn most_recent_of_data(sources: &[u16]) -> [u8; 20] {
+    let mut source_entries = [u16::MAX; MOST_RECENT_OF_CHAIN_SIZE];
+    source_entries[..sources.len()].copy_from_slice(sources);
+    MostRecentOfData {
+        source_entries,
+        max_divergence_bps: 100,
+        sources_max_age_s: 60,
+    }
+    .to_generic_data()
+}
+
+pub fn set_most_recent_of_entry(
+    oracle_mappings: &mut OracleMappings,
+    entry_id: usize,
+    sources: &[u16],
+) {
+    oracle_mappings.reset_entry(entry_id);
+    oracle_mappings.set_entry_mapping(
+        entry_id,
+        None,
+        OracleType::MostRecentOf,
+        most_recent_of_data(sources),
+    );
+}
+
+/// Scope TWAP (1h) entry of `source`
+pub fn set_twap_entry(oracle_mappings: &mut OracleMappings, entry_id: usize, source: u16) {
+    oracle_mappings.reset_entry(entry_id);
+    oracle_mappings.set_entry_mapping(entry_id, None, OracleType::ScopeTwap1h, [0; 20]);
+    oracle_mappings.twap_source_or_ref_price_tolerance_bps[entry_id] = source;
+}
+
+/// Owned backing storage of an `AccountInfo`
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+impl TestAccount {
+    pub fn new(owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            owner,
+            lamports: 0,
+            data,
+        }
+    }
+
+    /// Zeroed zero-copy account of the program, with its discriminator
+    pub fn zero_copy<T: ZeroCopy + Discriminator>() -> Self {
+        let mut data = T::discriminator().to_vec();
+        data.resize(8 + std::mem::size_of::<T>(), 0);
+        Self::new(crate::ID, data)
+    }
+
+    /// Writable, non signer, view of the account
+    pub fn account_info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}